@@ -0,0 +1,212 @@
+use crate::{ErrorKind, SerializableKubeError};
+use futures::future::BoxFuture;
+use http::header::AUTHORIZATION;
+use http::{HeaderValue, Request};
+use kube::client::ClientBuilder;
+use kube::config::{AuthInfo, ExecConfig, KubeConfigOptions, Kubeconfig};
+use kube::{Client, Config};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tower::buffer::BufferLayer;
+use tower::filter::{AsyncFilterLayer, AsyncPredicate};
+use tower::BoxError;
+
+/// Clients that have not been used for this long are dropped on the next lookup.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+struct PooledClient {
+    client: Client,
+    last_used: Instant,
+}
+
+static CLIENTS: Mutex<Option<HashMap<String, PooledClient>>> = Mutex::new(None);
+
+fn evict_idle_clients(clients: &mut HashMap<String, PooledClient>) {
+    clients.retain(|_, pooled| pooled.last_used.elapsed() < IDLE_TIMEOUT);
+}
+
+fn native_eks_auth_enabled() -> bool {
+    return crate::kubeconfig::read_settings()
+        .ok()
+        .and_then(|settings| settings["client"]["nativeEksAuth"].as_bool())
+        .unwrap_or(false);
+}
+
+#[derive(Clone)]
+enum NativeTokenSource {
+    Oidc(Box<AuthInfo>),
+    Eks(Box<ExecConfig>),
+}
+
+/// Sets the bearer token of every request from the OIDC or EKS token cache,
+/// which renews tokens shortly before they expire. Watches and log follows
+/// keep their client for as long as they run, so a token baked into the
+/// client would leave them with nothing but 401s once it expired.
+#[derive(Clone)]
+struct NativeToken {
+    context: String,
+    source: NativeTokenSource,
+}
+
+impl NativeToken {
+    async fn token(&self) -> Result<Option<String>, SerializableKubeError> {
+        return match &self.source {
+            NativeTokenSource::Oidc(auth_info) => Ok(crate::oidc::token_for_context(&self.context, auth_info)
+                .await?
+                .map(|token| token.id_token)),
+            NativeTokenSource::Eks(exec) => Ok(crate::eks::token_for_exec(exec)
+                .await?
+                .map(|token| token.token)),
+        };
+    }
+}
+
+impl AsyncPredicate<Request<hyper::Body>> for NativeToken {
+    type Future = BoxFuture<'static, Result<Request<hyper::Body>, BoxError>>;
+    type Request = Request<hyper::Body>;
+
+    fn check(&mut self, mut request: Request<hyper::Body>) -> Self::Future {
+        let native_token = self.clone();
+
+        return Box::pin(async move {
+            let token = native_token.token().await?.ok_or(SerializableKubeError::new(
+                ErrorKind::AuthExpired,
+                format!("No credentials available for context {}", native_token.context),
+            ))?;
+
+            let mut value = HeaderValue::try_from(format!("Bearer {}", token))?;
+            value.set_sensitive(true);
+            request.headers_mut().insert(AUTHORIZATION, value);
+
+            return Ok(request);
+        });
+    }
+}
+
+/// Takes over exec plugins and auth providers we can handle in-process:
+/// stored OIDC tokens and, when enabled, EKS tokens generated without the aws
+/// CLI. Returns `None` when the kubeconfig is left as is.
+async fn native_token(
+    kubeconfig: &mut Kubeconfig,
+    context: &str,
+) -> Result<Option<NativeToken>, SerializableKubeError> {
+    let user = match crate::kubeconfig::find_auth_info(kubeconfig, context) {
+        Ok(named) => named.name,
+        Err(_) => return Ok(None),
+    };
+
+    let auth_info = match kubeconfig
+        .auth_infos
+        .iter_mut()
+        .find(|named| named.name == user)
+        .and_then(|named| named.auth_info.as_mut())
+    {
+        Some(auth_info) => auth_info,
+        None => return Ok(None),
+    };
+
+    let source = if crate::oidc::oidc_config(auth_info).is_some() {
+        NativeTokenSource::Oidc(Box::new(auth_info.clone()))
+    } else {
+        match auth_info.exec.as_ref() {
+            Some(exec) if native_eks_auth_enabled() => NativeTokenSource::Eks(Box::new(exec.clone())),
+            _ => return Ok(None),
+        }
+    };
+    let native_token = NativeToken {
+        context: context.to_string(),
+        source,
+    };
+
+    // The first token is fetched up front, so login problems show up when the
+    // client is created and setups we can't handle keep their exec plugin.
+    if native_token.token().await?.is_none() {
+        return Ok(None);
+    }
+
+    auth_info.exec = None;
+    auth_info.auth_provider = None;
+    auth_info.token = None;
+    auth_info.token_file = None;
+
+    return Ok(Some(native_token));
+}
+
+async fn create_client(context: &str) -> Result<Client, SerializableKubeError> {
+    let options = KubeConfigOptions {
+        context: Some(context.to_string()),
+        cluster: None,
+        user: None,
+    };
+
+    let mut kubeconfig = crate::kubeconfig::load_kubeconfig()?.kubeconfig;
+    let native_token = native_token(&mut kubeconfig, context).await?;
+
+    let client_config = Config::from_custom_kubeconfig(kubeconfig, &options)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let builder = ClientBuilder::try_from(client_config).map_err(|err| SerializableKubeError::from(err))?;
+
+    // The token filter clones the service it wraps for every request, which
+    // the buffer makes cheap.
+    return Ok(match native_token {
+        Some(native_token) => builder
+            .with_layer(&BufferLayer::new(1024))
+            .with_layer(&AsyncFilterLayer::new(native_token))
+            .build(),
+        None => builder.build(),
+    });
+}
+
+/// Returns the pooled client for `context`, creating it on first use.
+pub async fn client_with_context(context: &str) -> Result<Client, SerializableKubeError> {
+    {
+        let mut clients = CLIENTS.lock().unwrap();
+        let clients = clients.get_or_insert_with(HashMap::new);
+        evict_idle_clients(clients);
+
+        if let Some(pooled) = clients.get_mut(context) {
+            pooled.last_used = Instant::now();
+            return Ok(pooled.client.clone());
+        }
+    }
+
+    let client = create_client(context).await?;
+
+    // Another request may have created a client for the same context while we
+    // were waiting, in which case that one is kept so all callers share it.
+    let mut clients = CLIENTS.lock().unwrap();
+    let pooled = clients
+        .get_or_insert_with(HashMap::new)
+        .entry(context.to_string())
+        .or_insert(PooledClient {
+            client,
+            last_used: Instant::now(),
+        });
+
+    return Ok(pooled.client.clone());
+}
+
+/// Drops the pooled client for `context` so the next request rebuilds it.
+pub fn invalidate_client(context: &str) {
+    if let Some(clients) = CLIENTS.lock().unwrap().as_mut() {
+        clients.remove(context);
+    }
+}
+
+/// Drops every pooled client.
+pub fn invalidate_all_clients() {
+    if let Some(clients) = CLIENTS.lock().unwrap().as_mut() {
+        clients.clear();
+    }
+}
+
+#[tauri::command]
+pub fn reset_client(context: Option<String>) {
+    match context {
+        Some(context) => invalidate_client(&context),
+        None => invalidate_all_clients(),
+    }
+}
@@ -0,0 +1,233 @@
+use crate::client_pool::{client_with_context, invalidate_client};
+use crate::kubeconfig::{arg_value, exec_env, find_auth_info, load_kubeconfig};
+use crate::{ErrorKind, SerializableKubeError};
+use kube::config::AuthInfo;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use tauri::Manager;
+
+/// The interactive login that renews the credentials an exec plugin hands out.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "provider", rename_all = "camelCase")]
+pub enum LoginProvider {
+    AwsSso { profile: Option<String> },
+    Gcloud,
+    AzureCli,
+    AzureDeveloperCli,
+    OidcLogin { command: String, args: Vec<String> },
+    Oidc { issuer_url: String },
+}
+
+#[derive(Clone, Serialize)]
+struct CredentialsRefreshedPayload {
+    context: String,
+    provider: LoginProvider,
+}
+
+/// Azure's kubelogin picks how to get a token with `--login`, or
+/// `AAD_LOGIN_METHOD`, and defaults to a device code. Only the CLI logins and
+/// the browser login can be renewed from here; service principals, managed
+/// and workload identities need no login, and a device code has to be shown
+/// to the user, which running the plugin in the background can't do.
+fn azure_login_provider(command: &str, args: &[String], env: &HashMap<String, String>) -> Option<LoginProvider> {
+    let mode = arg_value(args, "--login")
+        .or_else(|| arg_value(args, "-l"))
+        .or_else(|| env.get("AAD_LOGIN_METHOD").cloned())
+        .unwrap_or_else(|| "devicecode".to_string());
+
+    return match mode.as_str() {
+        "azurecli" => Some(LoginProvider::AzureCli),
+        "azd" => Some(LoginProvider::AzureDeveloperCli),
+        "interactive" => Some(LoginProvider::OidcLogin {
+            command: command.to_string(),
+            args: args.to_vec(),
+        }),
+        _ => None,
+    };
+}
+
+/// Works out which login renews the credentials of an exec plugin, based on
+/// the plugin binary and its arguments. `aws sso login` is only offered for
+/// profiles that sign in through SSO. OIDC users, whether configured as an
+/// auth provider or through kubelogin, are logged in natively.
+pub fn detect_login_provider(auth_info: &AuthInfo) -> Option<LoginProvider> {
+    if let Some(config) = crate::oidc::oidc_config(auth_info) {
+        return Some(LoginProvider::Oidc {
+            issuer_url: config.issuer_url,
+        });
+    }
+
+    let exec = auth_info.exec.as_ref()?;
+    let command = exec.command.as_ref()?;
+    let args = exec.args.clone().unwrap_or_default();
+    let binary = Path::new(command)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match binary.as_str() {
+        "aws" | "aws-iam-authenticator" => {
+            let profile = arg_value(&args, "--profile").or_else(|| exec_env(exec).get("AWS_PROFILE").cloned());
+            if !crate::eks::uses_sso(&crate::eks::AwsFiles::from_env(), profile.as_deref()) {
+                return None;
+            }
+            Some(LoginProvider::AwsSso { profile })
+        }
+        "gke-gcloud-auth-plugin" | "gcloud" => Some(LoginProvider::Gcloud),
+        "kubelogin" if args.first().map(String::as_str) == Some("get-token") => {
+            azure_login_provider(command, &args, &exec_env(exec))
+        }
+        "kubectl-oidc_login" | "kubelogin" => Some(LoginProvider::OidcLogin {
+            command: command.clone(),
+            args,
+        }),
+        "kubectl" if args.first().map(String::as_str) == Some("oidc-login") => {
+            Some(LoginProvider::OidcLogin {
+                command: command.clone(),
+                args,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn login_command(provider: &LoginProvider) -> Option<(String, Vec<String>)> {
+    match provider {
+        LoginProvider::AwsSso { profile } => {
+            let mut args = vec!["sso".to_string(), "login".to_string()];
+            if let Some(profile) = profile {
+                args.extend(["--profile".to_string(), profile.clone()]);
+            }
+            Some(("aws".to_string(), args))
+        }
+        LoginProvider::Gcloud => Some(("gcloud".to_string(), vec!["auth".to_string(), "login".to_string()])),
+        LoginProvider::AzureCli => Some(("az".to_string(), vec!["login".to_string()])),
+        LoginProvider::AzureDeveloperCli => Some(("azd".to_string(), vec!["auth".to_string(), "login".to_string()])),
+        // kubelogin opens the browser itself whenever its cached token is unusable,
+        // so running the plugin once is the login.
+        LoginProvider::OidcLogin { command, args } => Some((command.clone(), args.clone())),
+        LoginProvider::Oidc { .. } => None,
+    }
+}
+
+fn run_login(provider: &LoginProvider, env: HashMap<String, String>) -> Result<(), SerializableKubeError> {
+    let (command, args) = match login_command(provider) {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+
+    let output = Command::new(&command)
+        .args(&args)
+        .envs(env)
+        .output()
+        .map_err(|err| {
+            SerializableKubeError::new(
+                ErrorKind::ExecPluginFailed,
+                format!("Unable to start '{}': {}", command, err),
+            )
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(SerializableKubeError::new(
+            ErrorKind::ExecPluginFailed,
+            format!("Login with '{} {}' failed: {}", command, args.join(" "), stderr),
+        )
+        .with_details(serde_json::json!({
+            "command": command,
+            "exitCode": output.status.code(),
+            "stderr": stderr,
+        })));
+    }
+
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn get_login_provider(context: &str) -> Result<Option<LoginProvider>, SerializableKubeError> {
+    let kubeconfig = load_kubeconfig()?.kubeconfig;
+    let auth_info = find_auth_info(&kubeconfig, context)?;
+
+    return Ok(auth_info.auth_info.as_ref().and_then(detect_login_provider));
+}
+
+/// Runs the login that belongs to the context's exec plugin, then rebuilds the
+/// client and retries a request against the cluster to confirm the new
+/// credentials work before reporting success.
+#[tauri::command]
+pub async fn refresh_credentials(
+    app_handle: tauri::AppHandle,
+    context: &str,
+) -> Result<LoginProvider, SerializableKubeError> {
+    let kubeconfig = load_kubeconfig()?.kubeconfig;
+    let auth_info = find_auth_info(&kubeconfig, context)?;
+    let auth_info = auth_info.auth_info.ok_or(SerializableKubeError::new(
+        ErrorKind::ContextNotFound,
+        "Auth info not found".to_string(),
+    ))?;
+
+    let provider = detect_login_provider(&auth_info).ok_or(SerializableKubeError::new(
+        ErrorKind::ExecPluginFailed,
+        format!("Don't know how to log in again for context {}", context),
+    ))?;
+    let env = auth_info.exec.as_ref().map(exec_env).unwrap_or_default();
+
+    if let Some(config) = crate::oidc::oidc_config(&auth_info) {
+        crate::oidc::login(&app_handle, context, &config, None).await?;
+    } else {
+        let login_provider = provider.clone();
+        tauri::async_runtime::spawn_blocking(move || run_login(&login_provider, env))
+            .await
+            .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, format!("{:?}", err)))??;
+    }
+
+    invalidate_client(context);
+    client_with_context(context)
+        .await?
+        .apiserver_version()
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let _ = app_handle.emit_all(
+        "credentials_refreshed",
+        CredentialsRefreshedPayload {
+            context: context.to_string(),
+            provider: provider.clone(),
+        },
+    );
+
+    return Ok(provider);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kubelogin(args: &[&str], env: &[(&str, &str)]) -> Option<LoginProvider> {
+        let args: Vec<String> = ["get-token"].iter().chain(args).map(|arg| arg.to_string()).collect();
+        let env: HashMap<String, String> = env.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+
+        return azure_login_provider("kubelogin", &args, &env);
+    }
+
+    #[test]
+    fn kubelogin_login_follows_its_mode() {
+        assert_eq!(kubelogin(&["--login", "azurecli"], &[]), Some(LoginProvider::AzureCli));
+        assert_eq!(kubelogin(&["--login=azd"], &[]), Some(LoginProvider::AzureDeveloperCli));
+        assert_eq!(kubelogin(&[], &[("AAD_LOGIN_METHOD", "azurecli")]), Some(LoginProvider::AzureCli));
+        assert!(matches!(
+            kubelogin(&["-l", "interactive"], &[]),
+            Some(LoginProvider::OidcLogin { .. })
+        ));
+    }
+
+    #[test]
+    fn kubelogin_modes_without_an_interactive_login_have_none() {
+        for mode in ["spn", "msi", "workloadidentity", "ropc", "devicecode"] {
+            assert_eq!(kubelogin(&["--login", mode], &[]), None);
+        }
+        assert_eq!(kubelogin(&[], &[]), None);
+    }
+}
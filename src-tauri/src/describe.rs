@@ -0,0 +1,971 @@
+use crate::dynamic::{dynamic_api, resolve_api_resource};
+use crate::events::{list_events, EventSummary};
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::api::core::v1::{
+    ConfigMap, Container, ContainerStatus, Node, PersistentVolumeClaim, Pod, PodTemplateSpec,
+    Secret, Service, Volume,
+};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta, Time};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const NONE: &str = "<none>";
+
+#[derive(Clone, Serialize)]
+pub struct DescribeRow {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DescribeTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A titled block of key/value rows and tables, which may nest further
+/// sections, like the containers of a pod.
+#[derive(Clone, Default, Serialize)]
+pub struct DescribeSection {
+    pub title: String,
+    pub rows: Vec<DescribeRow>,
+    pub tables: Vec<DescribeTable>,
+    pub sections: Vec<DescribeSection>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DescribeDocument {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub sections: Vec<DescribeSection>,
+}
+
+impl DescribeSection {
+    fn new(title: &str) -> Self {
+        return DescribeSection {
+            title: title.to_string(),
+            ..DescribeSection::default()
+        };
+    }
+
+    fn row(mut self, key: &str, value: impl Into<String>) -> Self {
+        let value: String = value.into();
+        self.rows.push(DescribeRow {
+            key: key.to_string(),
+            value: if value.is_empty() { NONE.to_string() } else { value },
+        });
+        return self;
+    }
+
+    fn table(mut self, columns: &[&str], rows: Vec<Vec<String>>) -> Self {
+        self.tables.push(DescribeTable {
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            rows,
+        });
+        return self;
+    }
+
+    fn section(mut self, section: DescribeSection) -> Self {
+        self.sections.push(section);
+        return self;
+    }
+}
+
+fn or_none(value: Option<impl ToString>) -> String {
+    return value.map(|value| value.to_string()).unwrap_or_default();
+}
+
+fn format_map(map: Option<&BTreeMap<String, String>>) -> String {
+    return map
+        .iter()
+        .flat_map(|map| map.iter())
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+fn format_quantities(quantities: Option<&BTreeMap<String, Quantity>>) -> String {
+    return quantities
+        .iter()
+        .flat_map(|quantities| quantities.iter())
+        .map(|(key, value)| format!("{}: {}", key, value.0))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+fn format_time(time: Option<&Time>) -> String {
+    return time.map(|time| time.0.to_rfc3339()).unwrap_or_default();
+}
+
+fn format_selector(selector: Option<&LabelSelector>) -> String {
+    let selector = match selector {
+        Some(selector) => selector,
+        None => return String::new(),
+    };
+
+    let mut parts: Vec<String> = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    parts.extend(selector.match_expressions.iter().flatten().map(|expression| {
+        format!(
+            "{} {} ({})",
+            expression.key,
+            expression.operator,
+            expression.values.clone().unwrap_or_default().join(",")
+        )
+    }));
+
+    return parts.join(",");
+}
+
+fn metadata_section(metadata: &ObjectMeta) -> DescribeSection {
+    let controlled_by = metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.controller == Some(true))
+        .map(|owner| format!("{}/{}", owner.kind, owner.name));
+
+    let mut section = DescribeSection::new("Overview")
+        .row("Name", or_none(metadata.name.as_ref()))
+        .row("Namespace", or_none(metadata.namespace.as_ref()))
+        .row("Labels", format_map(metadata.labels.as_ref()))
+        .row("Annotations", format_map(metadata.annotations.as_ref()))
+        .row("Created", format_time(metadata.creation_timestamp.as_ref()));
+
+    if let Some(controlled_by) = controlled_by {
+        section = section.row("Controlled By", controlled_by);
+    }
+    if let Some(deleted) = metadata.deletion_timestamp.as_ref() {
+        section = section.row("Terminating Since", format_time(Some(deleted)));
+    }
+
+    return section;
+}
+
+/// Builds a conditions table from any `status.conditions` list, which has the
+/// same shape for nearly every kind, including custom resources.
+fn conditions_section(status: Option<&serde_json::Value>) -> Option<DescribeSection> {
+    let conditions = status?.get("conditions")?.as_array()?;
+    let text = |condition: &serde_json::Value, field: &str| {
+        condition.get(field).and_then(|value| value.as_str()).unwrap_or("").to_string()
+    };
+
+    let rows = conditions
+        .iter()
+        .map(|condition| {
+            vec![
+                text(condition, "type"),
+                text(condition, "status"),
+                text(condition, "reason"),
+                text(condition, "message"),
+                text(condition, "lastTransitionTime"),
+            ]
+        })
+        .collect();
+
+    return Some(DescribeSection::new("Conditions").table(
+        &["Type", "Status", "Reason", "Message", "Last Transition"],
+        rows,
+    ));
+}
+
+fn container_state(status: &ContainerStatus) -> String {
+    let state = match status.state.as_ref() {
+        Some(state) => state,
+        None => return String::new(),
+    };
+
+    if let Some(running) = &state.running {
+        return format!("Running since {}", format_time(running.started_at.as_ref()));
+    }
+    if let Some(waiting) = &state.waiting {
+        return format!("Waiting: {}", or_none(waiting.reason.as_ref()));
+    }
+    if let Some(terminated) = &state.terminated {
+        return format!(
+            "Terminated: {} (exit code {})",
+            or_none(terminated.reason.as_ref()),
+            terminated.exit_code
+        );
+    }
+
+    return String::new();
+}
+
+fn container_section(container: &Container, status: Option<&ContainerStatus>) -> DescribeSection {
+    let ports = container
+        .ports
+        .iter()
+        .flatten()
+        .map(|port| format!("{}/{}", port.container_port, port.protocol.clone().unwrap_or("TCP".to_string())))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let resources = container.resources.as_ref();
+
+    let mut section = DescribeSection::new(&container.name)
+        .row("Image", or_none(container.image.as_ref()))
+        .row("Ports", ports)
+        .row("Command", container.command.clone().unwrap_or_default().join(" "))
+        .row("Args", container.args.clone().unwrap_or_default().join(" "))
+        .row("Limits", format_quantities(resources.and_then(|resources| resources.limits.as_ref())))
+        .row("Requests", format_quantities(resources.and_then(|resources| resources.requests.as_ref())));
+
+    if let Some(status) = status {
+        section = section
+            .row("State", container_state(status))
+            .row("Ready", status.ready.to_string())
+            .row("Restart Count", status.restart_count.to_string());
+    }
+
+    let environment: Vec<Vec<String>> = container
+        .env
+        .iter()
+        .flatten()
+        .map(|env| {
+            let source = env.value_from.as_ref().map(|source| {
+                if let Some(secret) = &source.secret_key_ref {
+                    format!("secret {}/{}", secret.name.clone().unwrap_or_default(), secret.key)
+                } else if let Some(config_map) = &source.config_map_key_ref {
+                    format!("configmap {}/{}", config_map.name.clone().unwrap_or_default(), config_map.key)
+                } else if let Some(field) = &source.field_ref {
+                    format!("field {}", field.field_path)
+                } else {
+                    "resource field".to_string()
+                }
+            });
+            vec![env.name.clone(), env.value.clone().or(source).unwrap_or_default()]
+        })
+        .collect();
+    if !environment.is_empty() {
+        section = section.table(&["Variable", "Value"], environment);
+    }
+
+    let mounts: Vec<Vec<String>> = container
+        .volume_mounts
+        .iter()
+        .flatten()
+        .map(|mount| {
+            vec![
+                mount.name.clone(),
+                mount.mount_path.clone(),
+                if mount.read_only == Some(true) { "ro" } else { "rw" }.to_string(),
+            ]
+        })
+        .collect();
+    if !mounts.is_empty() {
+        section = section.table(&["Volume", "Mount Path", "Mode"], mounts);
+    }
+
+    return section;
+}
+
+fn containers_section(
+    title: &str,
+    containers: &[Container],
+    statuses: Option<&Vec<ContainerStatus>>,
+) -> DescribeSection {
+    let mut section = DescribeSection::new(title);
+    for container in containers {
+        let status = statuses
+            .iter()
+            .flat_map(|statuses| statuses.iter())
+            .find(|status| status.name == container.name);
+        section = section.section(container_section(container, status));
+    }
+
+    return section;
+}
+
+fn volume_source(volume: &Volume) -> (String, String) {
+    if let Some(claim) = &volume.persistent_volume_claim {
+        return ("PersistentVolumeClaim".to_string(), claim.claim_name.clone());
+    }
+    if let Some(config_map) = &volume.config_map {
+        return ("ConfigMap".to_string(), config_map.name.clone().unwrap_or_default());
+    }
+    if let Some(secret) = &volume.secret {
+        return ("Secret".to_string(), secret.secret_name.clone().unwrap_or_default());
+    }
+    if volume.empty_dir.is_some() {
+        return ("EmptyDir".to_string(), String::new());
+    }
+    if let Some(host_path) = &volume.host_path {
+        return ("HostPath".to_string(), host_path.path.clone());
+    }
+    if volume.projected.is_some() {
+        return ("Projected".to_string(), String::new());
+    }
+    if volume.downward_api.is_some() {
+        return ("DownwardAPI".to_string(), String::new());
+    }
+
+    return ("Other".to_string(), String::new());
+}
+
+fn volumes_section(volumes: Option<&Vec<Volume>>) -> Option<DescribeSection> {
+    let rows: Vec<Vec<String>> = volumes?
+        .iter()
+        .map(|volume| {
+            let (kind, source) = volume_source(volume);
+            vec![volume.name.clone(), kind, source]
+        })
+        .collect();
+
+    return Some(DescribeSection::new("Volumes").table(&["Name", "Type", "Source"], rows));
+}
+
+fn pod_template_sections(template: Option<&PodTemplateSpec>) -> Vec<DescribeSection> {
+    let template = match template {
+        Some(template) => template,
+        None => return Vec::new(),
+    };
+    let spec = template.spec.clone().unwrap_or_default();
+
+    let mut sections = vec![DescribeSection::new("Pod Template")
+        .row(
+            "Labels",
+            format_map(template.metadata.as_ref().and_then(|metadata| metadata.labels.as_ref())),
+        )
+        .row("Service Account", or_none(spec.service_account_name.as_ref()))];
+
+    if let Some(init_containers) = spec.init_containers.as_ref() {
+        sections.push(containers_section("Init Containers", init_containers, None));
+    }
+    sections.push(containers_section("Containers", &spec.containers, None));
+    sections.extend(volumes_section(spec.volumes.as_ref()));
+
+    return sections;
+}
+
+fn events_section(events: &[EventSummary]) -> DescribeSection {
+    let rows = events
+        .iter()
+        .rev()
+        .map(|event| {
+            vec![
+                or_none(event.type_.as_ref()),
+                or_none(event.reason.as_ref()),
+                event.last_seen.map(|time| time.to_rfc3339()).unwrap_or_default(),
+                or_none(event.reporting_controller.as_ref()),
+                event.count.to_string(),
+                or_none(event.note.as_ref()),
+            ]
+        })
+        .collect();
+
+    return DescribeSection::new("Events").table(
+        &["Type", "Reason", "Last Seen", "From", "Count", "Message"],
+        rows,
+    );
+}
+
+fn typed<K: DeserializeOwned>(object: &serde_json::Value) -> Result<K, SerializableKubeError> {
+    return serde_json::from_value(object.clone())
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Invalid, err.to_string()));
+}
+
+fn describe_pod(pod: Pod) -> Vec<DescribeSection> {
+    let spec = pod.spec.clone().unwrap_or_default();
+    let status = pod.status.clone().unwrap_or_default();
+
+    let mut sections = vec![metadata_section(&pod.metadata)
+        .row("Node", or_none(spec.node_name.as_ref()))
+        .row("Service Account", or_none(spec.service_account_name.as_ref()))
+        .row("Status", or_none(status.phase.as_ref()))
+        .row("IP", or_none(status.pod_ip.as_ref()))
+        .row("QoS Class", or_none(status.qos_class.as_ref()))
+        .row("Priority Class", or_none(spec.priority_class_name.as_ref()))
+        .row("Start Time", format_time(status.start_time.as_ref()))];
+
+    if let Some(init_containers) = spec.init_containers.as_ref() {
+        sections.push(containers_section(
+            "Init Containers",
+            init_containers,
+            status.init_container_statuses.as_ref(),
+        ));
+    }
+    sections.push(containers_section(
+        "Containers",
+        &spec.containers,
+        status.container_statuses.as_ref(),
+    ));
+    sections.extend(volumes_section(spec.volumes.as_ref()));
+
+    let tolerations = spec
+        .tolerations
+        .iter()
+        .flatten()
+        .map(|toleration| {
+            format!(
+                "{}{}:{}",
+                toleration.key.clone().unwrap_or_default(),
+                toleration.value.as_ref().map_or(String::new(), |value| format!("={}", value)),
+                toleration.effect.clone().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    sections.push(
+        DescribeSection::new("Scheduling")
+            .row("Node Selectors", format_map(spec.node_selector.as_ref()))
+            .row("Tolerations", tolerations),
+    );
+
+    return sections;
+}
+
+fn describe_deployment(deployment: Deployment) -> Vec<DescribeSection> {
+    let spec = deployment.spec.clone().unwrap_or_default();
+    let status = deployment.status.clone().unwrap_or_default();
+    let strategy = spec.strategy.clone().unwrap_or_default();
+
+    let mut overview = metadata_section(&deployment.metadata)
+        .row("Selector", format_selector(Some(&spec.selector)))
+        .row(
+            "Replicas",
+            format!(
+                "{} desired | {} updated | {} total | {} available | {} unavailable",
+                spec.replicas.unwrap_or(1),
+                status.updated_replicas.unwrap_or_default(),
+                status.replicas.unwrap_or_default(),
+                status.available_replicas.unwrap_or_default(),
+                status.unavailable_replicas.unwrap_or_default()
+            ),
+        )
+        .row("Strategy", or_none(strategy.type_.as_ref()))
+        .row("Min Ready Seconds", spec.min_ready_seconds.unwrap_or_default().to_string());
+
+    if let Some(rolling_update) = strategy.rolling_update.as_ref() {
+        overview = overview.row(
+            "Rolling Update Strategy",
+            format!(
+                "{} max unavailable, {} max surge",
+                rolling_update.max_unavailable.as_ref().map_or("25%".to_string(), int_or_string),
+                rolling_update.max_surge.as_ref().map_or("25%".to_string(), int_or_string)
+            ),
+        );
+    }
+    if spec.paused == Some(true) {
+        overview = overview.row("Paused", "true");
+    }
+
+    let mut sections = vec![overview];
+    sections.extend(pod_template_sections(Some(&spec.template)));
+    return sections;
+}
+
+fn int_or_string(value: &k8s_openapi::apimachinery::pkg::util::intstr::IntOrString) -> String {
+    return match value {
+        k8s_openapi::apimachinery::pkg::util::intstr::IntOrString::Int(value) => value.to_string(),
+        k8s_openapi::apimachinery::pkg::util::intstr::IntOrString::String(value) => value.clone(),
+    };
+}
+
+fn describe_statefulset(statefulset: StatefulSet) -> Vec<DescribeSection> {
+    let spec = statefulset.spec.clone().unwrap_or_default();
+    let status = statefulset.status.clone().unwrap_or_default();
+    let strategy = spec.update_strategy.clone().unwrap_or_default();
+
+    let mut sections = vec![metadata_section(&statefulset.metadata)
+        .row("Selector", format_selector(Some(&spec.selector)))
+        .row("Service Name", spec.service_name.clone())
+        .row(
+            "Replicas",
+            format!(
+                "{} desired | {} total | {} ready | {} updated",
+                spec.replicas.unwrap_or(1),
+                status.replicas,
+                status.ready_replicas.unwrap_or_default(),
+                status.updated_replicas.unwrap_or_default()
+            ),
+        )
+        .row("Update Strategy", or_none(strategy.type_.as_ref()))
+        .row(
+            "Partition",
+            or_none(strategy.rolling_update.and_then(|rolling_update| rolling_update.partition)),
+        )
+        .row("Pod Management Policy", or_none(spec.pod_management_policy.as_ref()))];
+
+    sections.extend(pod_template_sections(Some(&spec.template)));
+
+    let claims: Vec<Vec<String>> = spec
+        .volume_claim_templates
+        .iter()
+        .flatten()
+        .map(|claim| {
+            let claim_spec = claim.spec.clone().unwrap_or_default();
+            vec![
+                or_none(claim.metadata.name.as_ref()),
+                or_none(claim_spec.storage_class_name.as_ref()),
+                claim_spec.access_modes.unwrap_or_default().join(", "),
+                format_quantities(claim_spec.resources.as_ref().and_then(|resources| resources.requests.as_ref())),
+            ]
+        })
+        .collect();
+    if !claims.is_empty() {
+        sections.push(DescribeSection::new("Volume Claims").table(
+            &["Name", "Storage Class", "Access Modes", "Requests"],
+            claims,
+        ));
+    }
+
+    return sections;
+}
+
+fn describe_daemonset(daemonset: DaemonSet) -> Vec<DescribeSection> {
+    let spec = daemonset.spec.clone().unwrap_or_default();
+    let status = daemonset.status.clone().unwrap_or_default();
+
+    let mut sections = vec![metadata_section(&daemonset.metadata)
+        .row("Selector", format_selector(Some(&spec.selector)))
+        .row("Desired Number Scheduled", status.desired_number_scheduled.to_string())
+        .row("Current Number Scheduled", status.current_number_scheduled.to_string())
+        .row("Number Ready", status.number_ready.to_string())
+        .row("Number Available", status.number_available.unwrap_or_default().to_string())
+        .row("Up To Date", status.updated_number_scheduled.unwrap_or_default().to_string())
+        .row("Misscheduled", status.number_misscheduled.to_string())
+        .row(
+            "Update Strategy",
+            or_none(spec.update_strategy.and_then(|strategy| strategy.type_)),
+        )];
+
+    sections.extend(pod_template_sections(Some(&spec.template)));
+    return sections;
+}
+
+fn describe_replicaset(replicaset: ReplicaSet) -> Vec<DescribeSection> {
+    let spec = replicaset.spec.clone().unwrap_or_default();
+    let status = replicaset.status.clone().unwrap_or_default();
+
+    let mut sections = vec![metadata_section(&replicaset.metadata)
+        .row("Selector", format_selector(Some(&spec.selector)))
+        .row(
+            "Replicas",
+            format!(
+                "{} current / {} desired",
+                status.replicas,
+                spec.replicas.unwrap_or(1)
+            ),
+        )
+        .row(
+            "Pods Status",
+            format!(
+                "{} ready / {} available",
+                status.ready_replicas.unwrap_or_default(),
+                status.available_replicas.unwrap_or_default()
+            ),
+        )];
+
+    sections.extend(pod_template_sections(spec.template.as_ref()));
+    return sections;
+}
+
+fn describe_job(job: Job) -> Vec<DescribeSection> {
+    let spec = job.spec.clone().unwrap_or_default();
+    let status = job.status.clone().unwrap_or_default();
+
+    let mut sections = vec![metadata_section(&job.metadata)
+        .row("Selector", format_selector(spec.selector.as_ref()))
+        .row("Parallelism", or_none(spec.parallelism))
+        .row("Completions", or_none(spec.completions))
+        .row("Backoff Limit", or_none(spec.backoff_limit))
+        .row("Start Time", format_time(status.start_time.as_ref()))
+        .row("Completed At", format_time(status.completion_time.as_ref()))
+        .row(
+            "Pods Statuses",
+            format!(
+                "{} active / {} succeeded / {} failed",
+                status.active.unwrap_or_default(),
+                status.succeeded.unwrap_or_default(),
+                status.failed.unwrap_or_default()
+            ),
+        )];
+
+    sections.extend(pod_template_sections(Some(&spec.template)));
+    return sections;
+}
+
+fn describe_service(service: Service) -> Vec<DescribeSection> {
+    let spec = service.spec.clone().unwrap_or_default();
+    let load_balancer = service
+        .status
+        .as_ref()
+        .and_then(|status| status.load_balancer.as_ref())
+        .and_then(|load_balancer| load_balancer.ingress.as_ref())
+        .map(|ingresses| {
+            ingresses
+                .iter()
+                .filter_map(|ingress| ingress.ip.clone().or(ingress.hostname.clone()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        });
+
+    let ports = spec
+        .ports
+        .iter()
+        .flatten()
+        .map(|port| {
+            vec![
+                or_none(port.name.as_ref()),
+                port.port.to_string(),
+                port.target_port.as_ref().map(int_or_string).unwrap_or_default(),
+                or_none(port.node_port),
+                port.protocol.clone().unwrap_or("TCP".to_string()),
+            ]
+        })
+        .collect();
+
+    return vec![
+        metadata_section(&service.metadata)
+            .row("Type", or_none(spec.type_.as_ref()))
+            .row("Selector", format_map(spec.selector.as_ref()))
+            .row("IPs", spec.cluster_ips.clone().unwrap_or_default().join(", "))
+            .row("External IPs", spec.external_ips.clone().unwrap_or_default().join(", "))
+            .row("External Name", or_none(spec.external_name.as_ref()))
+            .row("Load Balancer Ingress", load_balancer.unwrap_or_default())
+            .row("Session Affinity", or_none(spec.session_affinity.as_ref())),
+        DescribeSection::new("Ports").table(
+            &["Name", "Port", "Target Port", "Node Port", "Protocol"],
+            ports,
+        ),
+    ];
+}
+
+fn describe_node(node: Node) -> Vec<DescribeSection> {
+    let spec = node.spec.clone().unwrap_or_default();
+    let status = node.status.clone().unwrap_or_default();
+
+    let roles = node
+        .metadata
+        .labels
+        .iter()
+        .flatten()
+        .filter_map(|(key, _)| key.strip_prefix("node-role.kubernetes.io/"))
+        .collect::<Vec<&str>>()
+        .join(", ");
+    let taints = spec
+        .taints
+        .iter()
+        .flatten()
+        .map(|taint| {
+            format!(
+                "{}{}:{}",
+                taint.key,
+                taint.value.as_ref().map_or(String::new(), |value| format!("={}", value)),
+                taint.effect
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut sections = vec![metadata_section(&node.metadata)
+        .row("Roles", roles)
+        .row("Unschedulable", spec.unschedulable.unwrap_or(false).to_string())
+        .row("Taints", taints)
+        .row("Pod CIDR", or_none(spec.pod_cidr.as_ref()))
+        .row("Provider ID", or_none(spec.provider_id.as_ref()))];
+
+    let addresses = status
+        .addresses
+        .iter()
+        .flatten()
+        .map(|address| vec![address.type_.clone(), address.address.clone()])
+        .collect();
+    sections.push(DescribeSection::new("Addresses").table(&["Type", "Address"], addresses));
+
+    sections.push(
+        DescribeSection::new("Resources")
+            .row("Capacity", format_quantities(status.capacity.as_ref()))
+            .row("Allocatable", format_quantities(status.allocatable.as_ref())),
+    );
+
+    if let Some(info) = status.node_info.as_ref() {
+        sections.push(
+            DescribeSection::new("System Info")
+                .row("OS Image", info.os_image.clone())
+                .row("Operating System", info.operating_system.clone())
+                .row("Architecture", info.architecture.clone())
+                .row("Kernel Version", info.kernel_version.clone())
+                .row("Container Runtime", info.container_runtime_version.clone())
+                .row("Kubelet Version", info.kubelet_version.clone())
+                .row("Kube-Proxy Version", info.kube_proxy_version.clone()),
+        );
+    }
+
+    return sections;
+}
+
+fn describe_configmap(configmap: ConfigMap) -> Vec<DescribeSection> {
+    let mut data = DescribeSection::new("Data");
+    for (key, value) in configmap.data.iter().flatten() {
+        data = data.row(key, value.clone());
+    }
+    for (key, value) in configmap.binary_data.iter().flatten() {
+        data = data.row(key, format!("{} bytes of binary data", value.0.len()));
+    }
+
+    return vec![metadata_section(&configmap.metadata), data];
+}
+
+/// Only the size of secret values is shown, like `kubectl describe`.
+fn describe_secret(secret: Secret) -> Vec<DescribeSection> {
+    let mut data = DescribeSection::new("Data");
+    for (key, value) in secret.data.iter().flatten() {
+        data = data.row(key, format!("{} bytes", value.0.len()));
+    }
+
+    return vec![
+        metadata_section(&secret.metadata).row("Type", or_none(secret.type_.as_ref())),
+        data,
+    ];
+}
+
+fn describe_persistentvolumeclaim(claim: PersistentVolumeClaim) -> Vec<DescribeSection> {
+    let spec = claim.spec.clone().unwrap_or_default();
+    let status = claim.status.clone().unwrap_or_default();
+
+    return vec![metadata_section(&claim.metadata)
+        .row("Storage Class", or_none(spec.storage_class_name.as_ref()))
+        .row("Status", or_none(status.phase.as_ref()))
+        .row("Volume", or_none(spec.volume_name.as_ref()))
+        .row("Capacity", format_quantities(status.capacity.as_ref()))
+        .row("Access Modes", status.access_modes.clone().unwrap_or_default().join(", "))
+        .row("Volume Mode", or_none(spec.volume_mode.as_ref()))];
+}
+
+/// Flattens the scalar fields of a json document into rows keyed by their
+/// path, for kinds we don't know the layout of.
+fn flatten_rows(prefix: &str, value: &serde_json::Value, section: DescribeSection) -> DescribeSection {
+    match value {
+        serde_json::Value::Object(map) => map.iter().fold(section, |section, (key, value)| {
+            if prefix.is_empty() && key == "conditions" {
+                return section;
+            }
+            let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+            flatten_rows(&path, value, section)
+        }),
+        serde_json::Value::Array(items) => items.iter().enumerate().fold(section, |section, (index, item)| {
+            flatten_rows(&format!("{}[{}]", prefix, index), item, section)
+        }),
+        serde_json::Value::String(text) => section.row(prefix, text.clone()),
+        serde_json::Value::Null => section,
+        other => section.row(prefix, other.to_string()),
+    }
+}
+
+fn describe_generic(metadata: &ObjectMeta, object: &serde_json::Value) -> Vec<DescribeSection> {
+    let mut sections = vec![metadata_section(metadata)];
+
+    for (field, title) in [("spec", "Spec"), ("data", "Data"), ("status", "Status")] {
+        if let Some(value) = object.get(field) {
+            sections.push(flatten_rows("", value, DescribeSection::new(title)));
+        }
+    }
+
+    return sections;
+}
+
+/// Builds a structured describe document for any object: dedicated layouts
+/// for the core kinds, a flattened view of spec and status for everything
+/// else, plus its conditions and events.
+#[tauri::command]
+pub async fn describe_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<DescribeDocument, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let (resource, capabilities) =
+        resolve_api_resource(&client, context, group, version, kind).await?;
+
+    let object = dynamic_api(client, &resource, &capabilities, namespace)
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+    let metadata = object.metadata.clone();
+    let value = serde_json::to_value(&object)
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))?;
+
+    let mut sections = match (resource.group.as_str(), resource.kind.as_str()) {
+        ("", "Pod") => describe_pod(typed(&value)?),
+        ("apps", "Deployment") => describe_deployment(typed(&value)?),
+        ("apps", "StatefulSet") => describe_statefulset(typed(&value)?),
+        ("apps", "DaemonSet") => describe_daemonset(typed(&value)?),
+        ("apps", "ReplicaSet") => describe_replicaset(typed(&value)?),
+        ("batch", "Job") => describe_job(typed(&value)?),
+        ("", "Service") => describe_service(typed(&value)?),
+        ("", "Node") => describe_node(typed(&value)?),
+        ("", "ConfigMap") => describe_configmap(typed(&value)?),
+        ("", "Secret") => describe_secret(typed(&value)?),
+        ("", "PersistentVolumeClaim") => describe_persistentvolumeclaim(typed(&value)?),
+        _ => describe_generic(&metadata, &value),
+    };
+
+    sections.extend(conditions_section(value.get("status")));
+
+    let events = list_events(
+        context,
+        metadata.namespace.as_deref().unwrap_or(""),
+        "",
+        "",
+        metadata.uid.as_deref().unwrap_or(""),
+    )
+    .await
+    .unwrap_or_default();
+    sections.push(events_section(&events));
+
+    return Ok(DescribeDocument {
+        kind: resource.kind,
+        name: name.to_string(),
+        namespace: metadata.namespace,
+        sections,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section<'a>(sections: &'a [DescribeSection], title: &str) -> &'a DescribeSection {
+        return sections
+            .iter()
+            .find(|section| section.title == title)
+            .unwrap_or_else(|| panic!("no {} section", title));
+    }
+
+    fn row<'a>(section: &'a DescribeSection, key: &str) -> &'a str {
+        return section
+            .rows
+            .iter()
+            .find(|row| row.key == key)
+            .map(|row| row.value.as_str())
+            .unwrap_or_else(|| panic!("no {} row in {}", key, section.title));
+    }
+
+    #[test]
+    fn pod_document_lists_containers_with_their_state() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "web-0",
+                "namespace": "shop",
+                "labels": { "app": "web" },
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1", "kind": "StatefulSet", "name": "web", "uid": "1", "controller": true,
+                }],
+            },
+            "spec": {
+                "nodeName": "node-a",
+                "containers": [{
+                    "name": "app",
+                    "image": "shop/web:1.2",
+                    "ports": [{ "containerPort": 8080 }],
+                    "env": [
+                        { "name": "MODE", "value": "production" },
+                        { "name": "TOKEN", "valueFrom": { "secretKeyRef": { "name": "api", "key": "token" } } },
+                    ],
+                }],
+            },
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [{
+                    "name": "app",
+                    "image": "shop/web:1.2",
+                    "imageID": "",
+                    "ready": false,
+                    "restartCount": 3,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                }],
+            },
+        }))
+        .unwrap();
+
+        let sections = describe_pod(pod);
+
+        let overview = section(&sections, "Overview");
+        assert_eq!(row(overview, "Name"), "web-0");
+        assert_eq!(row(overview, "Labels"), "app=web");
+        assert_eq!(row(overview, "Controlled By"), "StatefulSet/web");
+        assert_eq!(row(overview, "Node"), "node-a");
+        assert_eq!(row(overview, "IP"), NONE);
+
+        let app = section(&section(&sections, "Containers").sections, "app");
+        assert_eq!(row(app, "Image"), "shop/web:1.2");
+        assert_eq!(row(app, "Ports"), "8080/TCP");
+        assert_eq!(row(app, "State"), "Waiting: CrashLoopBackOff");
+        assert_eq!(row(app, "Restart Count"), "3");
+        assert_eq!(
+            app.tables[0].rows,
+            vec![
+                vec!["MODE".to_string(), "production".to_string()],
+                vec!["TOKEN".to_string(), "secret api/token".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn secret_document_only_shows_value_sizes() {
+        let secret: Secret = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "api", "namespace": "shop" },
+            "type": "Opaque",
+            "data": { "token": "c2VjcmV0LXZhbHVl" },
+        }))
+        .unwrap();
+
+        let sections = describe_secret(secret);
+
+        assert_eq!(row(section(&sections, "Overview"), "Type"), "Opaque");
+        assert_eq!(row(section(&sections, "Data"), "token"), "12 bytes");
+
+        let document = serde_json::to_string(&sections).unwrap();
+        assert!(!document.contains("secret-value"));
+        assert!(!document.contains("c2VjcmV0LXZhbHVl"));
+    }
+
+    #[test]
+    fn generic_document_flattens_spec_and_status() {
+        let object = serde_json::json!({
+            "apiVersion": "example.com/v1",
+            "kind": "Backup",
+            "metadata": { "name": "nightly", "namespace": "shop" },
+            "spec": {
+                "schedule": "0 2 * * *",
+                "retention": { "days": 7 },
+                "targets": ["db", "files"],
+                "suspend": false,
+            },
+            "status": {
+                "lastRun": "2024-01-01T02:00:00Z",
+                "conditions": [{ "type": "Ready", "status": "True" }],
+            },
+        });
+        let metadata: ObjectMeta = serde_json::from_value(object["metadata"].clone()).unwrap();
+
+        let sections = describe_generic(&metadata, &object);
+
+        assert_eq!(row(section(&sections, "Overview"), "Name"), "nightly");
+
+        let spec = section(&sections, "Spec");
+        assert_eq!(row(spec, "schedule"), "0 2 * * *");
+        assert_eq!(row(spec, "retention.days"), "7");
+        assert_eq!(row(spec, "targets[1]"), "files");
+        assert_eq!(row(spec, "suspend"), "false");
+
+        // Conditions get their own table from `conditions_section`.
+        let status = section(&sections, "Status");
+        assert_eq!(row(status, "lastRun"), "2024-01-01T02:00:00Z");
+        assert!(status.rows.iter().all(|row| !row.key.starts_with("conditions")));
+        assert!(sections.iter().all(|section| section.title != "Data"));
+    }
+}
@@ -0,0 +1,654 @@
+use crate::{client_with_context, DeletionResult, ErrorKind, SerializableKubeError};
+use either::Either;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
+use kube::api::{
+    Api, ApiResource, DeleteParams, DynamicObject, ListParams, Patch, PatchParams, PostParams,
+};
+use kube::core::GroupVersion;
+use kube::discovery::{self, ApiCapabilities, Discovery, Scope};
+use kube::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+static API_RESOURCES: Mutex<Option<HashMap<String, (ApiResource, ApiCapabilities)>>> =
+    Mutex::new(None);
+
+fn matches_resource(resource: &ApiResource, kind: &str) -> bool {
+    resource.kind.eq_ignore_ascii_case(kind) || resource.plural.eq_ignore_ascii_case(kind)
+}
+
+/// Resolves a kind or plural name to an `ApiResource`.
+///
+/// `group` and `version` may be left empty, in which case the cluster's
+/// preferred version is used and, without a group, the core group is searched
+/// before any other api group.
+pub async fn resolve_api_resource(
+    client: &Client,
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+) -> Result<(ApiResource, ApiCapabilities), SerializableKubeError> {
+    let cache_key = format!("{}/{}/{}/{}", context, group, version, kind);
+    if let Some(resolved) = API_RESOURCES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|resources| resources.get(&cache_key))
+    {
+        return Ok(resolved.clone());
+    }
+
+    let candidates = if !version.is_empty() {
+        let group_version = GroupVersion::gv(group, version);
+        discovery::pinned_group(client, &group_version)
+            .await
+            .map_err(|err| SerializableKubeError::from(err))?
+            .versioned_resources(version)
+    } else if !group.is_empty() {
+        discovery::group(client, group)
+            .await
+            .map_err(|err| SerializableKubeError::from(err))?
+            .recommended_resources()
+    } else {
+        let discovery = Discovery::new(client.clone())
+            .run()
+            .await
+            .map_err(|err| SerializableKubeError::from(err))?;
+
+        let mut groups = discovery.groups_alphabetical();
+        groups.sort_by_key(|group| group.name() != discovery::ApiGroup::CORE_GROUP);
+        groups
+            .into_iter()
+            .flat_map(|group| group.recommended_resources())
+            .collect()
+    };
+
+    let resolved = candidates
+        .into_iter()
+        .find(|(resource, _)| matches_resource(resource, kind))
+        .ok_or(SerializableKubeError {
+            kind: ErrorKind::NotFound,
+            message: format!("Resource {} not found", kind),
+            code: Some(404),
+            reason: Some("NotFound".to_string()),
+            details: None,
+        })?;
+
+    API_RESOURCES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(cache_key, resolved.clone());
+
+    return Ok(resolved);
+}
+
+/// Forgets the resolved api resources of `context`, e.g. after its cluster changed.
+pub fn invalidate_api_resources(context: &str) {
+    if let Some(resources) = API_RESOURCES.lock().unwrap().as_mut() {
+        let prefix = format!("{}/", context);
+        resources.retain(|key, _| !key.starts_with(&prefix));
+    }
+}
+
+/// Builds an `Api<DynamicObject>`, ignoring `namespace` for cluster scoped resources.
+pub fn dynamic_api(
+    client: Client,
+    resource: &ApiResource,
+    capabilities: &ApiCapabilities,
+    namespace: &str,
+) -> Api<DynamicObject> {
+    if capabilities.scope == Scope::Cluster || namespace.is_empty() {
+        return Api::all_with(client, resource);
+    }
+
+    return Api::namespaced_with(client, namespace, resource);
+}
+
+async fn api_for(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+) -> Result<Api<DynamicObject>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let (resource, capabilities) =
+        resolve_api_resource(&client, context, group, version, kind).await?;
+
+    return Ok(dynamic_api(client, &resource, &capabilities, namespace));
+}
+
+#[tauri::command]
+pub async fn list_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    label_selector: &str,
+    field_selector: &str,
+) -> Result<Vec<DynamicObject>, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    let mut list_params = ListParams::default();
+    if !label_selector.is_empty() {
+        list_params = list_params.labels(label_selector);
+    }
+    if !field_selector.is_empty() {
+        list_params = list_params.fields(field_selector);
+    }
+
+    return resource_api
+        .list(&list_params)
+        .await
+        .map(|resources| resources.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn get_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<DynamicObject, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    return resource_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn replace_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    object: DynamicObject,
+) -> Result<DynamicObject, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    return resource_api
+        .replace(name, &PostParams::default(), &object)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn patch_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    patch_type: &str,
+    patch: serde_json::Value,
+) -> Result<DynamicObject, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    let patch = match patch_type {
+        "json" => Patch::Json(serde_json::from_value::<json_patch::Patch>(patch).map_err(
+            |err| SerializableKubeError {
+                kind: ErrorKind::Invalid,
+                message: err.to_string(),
+                code: Some(422),
+                reason: Some("Invalid".to_string()),
+                details: None,
+            },
+        )?),
+        "strategic" => Patch::Strategic(patch),
+        "merge" => Patch::Merge(patch),
+        _ => {
+            return Err(SerializableKubeError::new(
+                ErrorKind::Invalid,
+                format!("Unknown patch type {}, expected json, strategic or merge", patch_type),
+            ))
+        }
+    };
+
+    return resource_api
+        .patch(name, &PatchParams::default(), &patch)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+/// The field manager for every server-side apply, so the fields we own show
+/// up under our name in `managedFields`.
+pub const FIELD_MANAGER: &str = "jet-pilot";
+
+/// Server-side applies `object`. Without `force` the apply fails with a
+/// `Conflict` error listing the conflicting managers and field paths in its
+/// details whenever another manager owns a field we change.
+#[tauri::command]
+pub async fn apply_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    mut object: DynamicObject,
+    force: bool,
+) -> Result<DynamicObject, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    // The api server refuses applies that carry managedFields, and a stale
+    // resourceVersion would turn every apply after a controller update into a conflict.
+    object.metadata.managed_fields = None;
+    object.metadata.resource_version = None;
+
+    let mut patch_params = PatchParams::apply(FIELD_MANAGER);
+    if force {
+        patch_params = patch_params.force();
+    }
+
+    return resource_api
+        .patch(name, &patch_params, &Patch::Apply(&object))
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+/// Fields the api server rewrites on every write, which would otherwise show
+/// up in every preview.
+const IGNORED_DIFF_PATHS: [&str; 3] = [
+    ".metadata.managedFields",
+    ".metadata.resourceVersion",
+    ".metadata.generation",
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffOperation {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffEntry {
+    pub path: String,
+    pub operation: DiffOperation,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChangePreview {
+    pub live: serde_json::Value,
+    pub result: serde_json::Value,
+    pub diff: Vec<DiffEntry>,
+}
+
+fn named_items(items: &[serde_json::Value]) -> Option<Vec<(String, &serde_json::Value)>> {
+    return items
+        .iter()
+        .map(|item| Some((item.get("name")?.as_str()?.to_string(), item)))
+        .collect();
+}
+
+fn find_named<'a>(items: &[(String, &'a serde_json::Value)], name: &str) -> Option<&'a serde_json::Value> {
+    return items
+        .iter()
+        .find(|(item_name, _)| item_name == name)
+        .map(|(_, item)| *item);
+}
+
+/// Compares two json documents. Lists of named items, like containers or
+/// ports, are matched by name so reordering them doesn't mark every entry.
+pub fn diff_values(
+    path: &str,
+    old: Option<&serde_json::Value>,
+    new: Option<&serde_json::Value>,
+    diff: &mut Vec<DiffEntry>,
+) {
+    if IGNORED_DIFF_PATHS.contains(&path) {
+        return;
+    }
+
+    match (old, new) {
+        (None, None) => {}
+        (Some(old), None) => diff.push(DiffEntry {
+            path: path.to_string(),
+            operation: DiffOperation::Removed,
+            old: Some(old.clone()),
+            new: None,
+        }),
+        (None, Some(new)) => diff.push(DiffEntry {
+            path: path.to_string(),
+            operation: DiffOperation::Added,
+            old: None,
+            new: Some(new.clone()),
+        }),
+        (Some(serde_json::Value::Object(old)), Some(serde_json::Value::Object(new))) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                diff_values(&format!("{}.{}", path, key), old.get(key), new.get(key), diff);
+            }
+        }
+        (Some(serde_json::Value::Array(old)), Some(serde_json::Value::Array(new))) => {
+            match (named_items(old), named_items(new)) {
+                (Some(old_items), Some(new_items)) => {
+                    let mut names: Vec<&String> = Vec::new();
+                    for (name, _) in old_items.iter().chain(new_items.iter()) {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+
+                    for name in names {
+                        diff_values(
+                            &format!("{}[name={}]", path, name),
+                            find_named(&old_items, name),
+                            find_named(&new_items, name),
+                            diff,
+                        );
+                    }
+                }
+                _ => {
+                    for index in 0..old.len().max(new.len()) {
+                        diff_values(
+                            &format!("{}[{}]", path, index),
+                            old.get(index),
+                            new.get(index),
+                            diff,
+                        );
+                    }
+                }
+            }
+        }
+        (Some(old), Some(new)) => {
+            if old != new {
+                diff.push(DiffEntry {
+                    path: path.to_string(),
+                    operation: DiffOperation::Changed,
+                    old: Some(old.clone()),
+                    new: Some(new.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Runs a replace, or a server-side apply when `method` is "apply", with
+/// `dryRun=All` and returns how the stored object would change, after
+/// defaulting, admission and mutating webhooks.
+#[tauri::command]
+pub async fn preview_change(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    method: &str,
+    mut object: DynamicObject,
+) -> Result<ChangePreview, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    let live = resource_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let result = match method {
+        "apply" => {
+            object.metadata.managed_fields = None;
+            object.metadata.resource_version = None;
+
+            resource_api
+                .patch(
+                    name,
+                    &PatchParams::apply(FIELD_MANAGER).dry_run(),
+                    &Patch::Apply(&object),
+                )
+                .await
+        }
+        _ => {
+            let post_params = PostParams {
+                dry_run: true,
+                ..PostParams::default()
+            };
+            resource_api.replace(name, &post_params, &object).await
+        }
+    }
+    .map_err(|err| SerializableKubeError::from(err))?;
+
+    let live = serde_json::to_value(&live)
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))?;
+    let result = serde_json::to_value(&result)
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))?;
+
+    let mut diff: Vec<DiffEntry> = Vec::new();
+    diff_values("", Some(&live), Some(&result), &mut diff);
+
+    return Ok(ChangePreview { live, result, diff });
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaleResult {
+    pub previous_replicas: i32,
+    pub replicas: i32,
+}
+
+/// Returns the HorizontalPodAutoscaler that targets `name`, if any.
+pub async fn find_autoscaler(
+    client: Client,
+    resource: &ApiResource,
+    namespace: &str,
+    name: &str,
+) -> Result<Option<HorizontalPodAutoscaler>, SerializableKubeError> {
+    if namespace.is_empty() {
+        return Ok(None);
+    }
+
+    let autoscaler_api: Api<HorizontalPodAutoscaler> = Api::namespaced(client, namespace);
+    let autoscalers = autoscaler_api
+        .list(&ListParams::default())
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    return Ok(autoscalers.items.into_iter().find(|autoscaler| {
+        autoscaler.spec.as_ref().is_some_and(|spec| {
+            let target = &spec.scale_target_ref;
+            let target_group = target
+                .api_version
+                .as_deref()
+                .and_then(|api_version| api_version.split_once('/'))
+                .map(|(group, _)| group)
+                .unwrap_or("");
+
+            target.kind == resource.kind && target.name == name && target_group == resource.group
+        })
+    }));
+}
+
+/// Sets the replica count through the `/scale` subresource, which works for
+/// every workload that supports scaling, including custom resources. Targets
+/// managed by a HorizontalPodAutoscaler are only scaled when `force` is set,
+/// as the autoscaler would undo the change anyway.
+#[tauri::command]
+pub async fn scale_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    replicas: i32,
+    force: bool,
+) -> Result<ScaleResult, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let (resource, capabilities) =
+        resolve_api_resource(&client, context, group, version, kind).await?;
+
+    if !capabilities
+        .subresources
+        .iter()
+        .any(|(subresource, _)| subresource.plural == "scale")
+    {
+        return Err(SerializableKubeError::new(
+            ErrorKind::Invalid,
+            format!("{} does not support scaling", resource.kind),
+        ));
+    }
+
+    if !force {
+        if let Some(autoscaler) =
+            find_autoscaler(client.clone(), &resource, namespace, name).await?
+        {
+            return Err(SerializableKubeError::new(
+                ErrorKind::Conflict,
+                format!(
+                    "{} {} is managed by HorizontalPodAutoscaler {}",
+                    resource.kind,
+                    name,
+                    autoscaler.metadata.name.clone().unwrap_or_default()
+                ),
+            )
+            .with_details(serde_json::json!({
+                "horizontalPodAutoscaler": autoscaler.metadata.name,
+            })));
+        }
+    }
+
+    let resource_api = dynamic_api(client, &resource, &capabilities, namespace);
+
+    let previous_replicas = resource_api
+        .get_scale(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?
+        .spec
+        .and_then(|spec| spec.replicas)
+        .unwrap_or_default();
+
+    let scale = resource_api
+        .patch_scale(
+            name,
+            &PatchParams::default(),
+            &Patch::Merge(serde_json::json!({ "spec": { "replicas": replicas } })),
+        )
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    return Ok(ScaleResult {
+        previous_replicas,
+        replicas: scale.spec.and_then(|spec| spec.replicas).unwrap_or(replicas),
+    });
+}
+
+#[tauri::command]
+pub async fn delete_resource(
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    grace_period_seconds: Option<u32>,
+) -> Result<DeletionResult, SerializableKubeError> {
+    let resource_api = api_for(context, group, version, kind, namespace).await?;
+
+    let delete_params = match grace_period_seconds {
+        Some(grace_period_seconds) => DeleteParams::default().grace_period(grace_period_seconds),
+        None => DeleteParams::default(),
+    };
+
+    match resource_api.delete(name, &delete_params).await {
+        Ok(Either::Left(_resource)) => Ok(DeletionResult::Deleted(name.to_string())),
+        Ok(Either::Right(_status)) => {
+            Ok(DeletionResult::Pending("Deletion in progress".to_string()))
+        }
+        Err(err) => Err(SerializableKubeError::from(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diff(old: serde_json::Value, new: serde_json::Value) -> Vec<(String, DiffOperation)> {
+        let mut diff = Vec::new();
+        diff_values("", Some(&old), Some(&new), &mut diff);
+
+        return diff.into_iter().map(|entry| (entry.path, entry.operation)).collect();
+    }
+
+    #[test]
+    fn objects_are_compared_per_key() {
+        assert_eq!(
+            diff(
+                json!({ "spec": { "replicas": 2, "paused": true }, "data": { "a": "1" } }),
+                json!({ "spec": { "replicas": 3 }, "data": { "a": "1", "b": "2" } }),
+            ),
+            vec![
+                (".data.b".to_string(), DiffOperation::Added),
+                (".spec.paused".to_string(), DiffOperation::Removed),
+                (".spec.replicas".to_string(), DiffOperation::Changed),
+            ]
+        );
+    }
+
+    #[test]
+    fn named_items_are_matched_by_name() {
+        let old = json!({ "containers": [
+            { "name": "web", "image": "web:1" },
+            { "name": "proxy", "image": "envoy:1" },
+        ] });
+        let new = json!({ "containers": [
+            { "name": "proxy", "image": "envoy:1" },
+            { "name": "web", "image": "web:2" },
+            { "name": "metrics", "image": "exporter:1" },
+        ] });
+
+        assert_eq!(
+            diff(old, new),
+            vec![
+                (".containers[name=web].image".to_string(), DiffOperation::Changed),
+                (".containers[name=metrics]".to_string(), DiffOperation::Added),
+            ]
+        );
+    }
+
+    #[test]
+    fn other_lists_are_compared_by_index() {
+        assert_eq!(
+            diff(json!({ "args": ["a", "b", "c"] }), json!({ "args": ["a", "x"] })),
+            vec![
+                (".args[1]".to_string(), DiffOperation::Changed),
+                (".args[2]".to_string(), DiffOperation::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn server_managed_fields_are_ignored() {
+        assert!(diff(
+            json!({ "metadata": { "resourceVersion": "1", "generation": 1, "managedFields": [] } }),
+            json!({ "metadata": { "resourceVersion": "2", "generation": 2 } }),
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn type_changes_are_a_single_change() {
+        assert_eq!(
+            diff(json!({ "port": "8080" }), json!({ "port": { "number": 8080 } })),
+            vec![(".port".to_string(), DiffOperation::Changed)]
+        );
+    }
+}
@@ -0,0 +1,737 @@
+use crate::kubeconfig::{arg_value, exec_env};
+use crate::{ErrorKind, SerializableKubeError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use kube::config::ExecConfig;
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::api::path;
+
+/// Tokens are valid for 15 minutes, the aws CLI reports them as expiring after 14.
+const TOKEN_LIFETIME_MINUTES: i64 = 14;
+const TOKEN_PREFIX: &str = "k8s-aws-v1.";
+/// How many `source_profile` hops are followed, which also ends cycles.
+const MAX_SOURCE_PROFILES: usize = 5;
+
+static TOKENS: Mutex<Option<HashMap<String, EksToken>>> = Mutex::new(None);
+
+#[derive(Clone, Debug)]
+pub struct EksToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+/// What `aws eks get-token` was asked for in the kubeconfig.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EksTokenRequest {
+    pub cluster_name: String,
+    pub region: Option<String>,
+    pub profile: Option<String>,
+}
+
+/// Locations of the aws CLI's files, so they can point at fixtures.
+pub struct AwsFiles {
+    pub config: PathBuf,
+    pub credentials: PathBuf,
+    pub sso_cache: PathBuf,
+}
+
+impl AwsFiles {
+    pub fn from_env() -> Self {
+        let aws_dir = path::home_dir().unwrap_or_default().join(".aws");
+
+        return AwsFiles {
+            config: env::var("AWS_CONFIG_FILE")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| aws_dir.join("config")),
+            credentials: env::var("AWS_SHARED_CREDENTIALS_FILE")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| aws_dir.join("credentials")),
+            sso_cache: aws_dir.join("sso").join("cache"),
+        };
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SsoCachedToken {
+    access_token: String,
+    expires_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoleCredentialsResponse {
+    role_credentials: RoleCredentials,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoleCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+}
+
+/// Recognises `aws eks get-token` exec plugins. Anything the native provider
+/// can't reproduce, like `--role-arn`, is left to the aws CLI.
+pub fn parse_exec(exec: &ExecConfig) -> Option<EksTokenRequest> {
+    let command = exec.command.as_ref()?;
+    let binary = Path::new(command).file_stem()?.to_string_lossy().to_string();
+    let args = exec.args.clone().unwrap_or_default();
+
+    if binary != "aws"
+        || !args.windows(2).any(|pair| pair[0] == "eks" && pair[1] == "get-token")
+        || arg_value(&args, "--role-arn").is_some()
+    {
+        return None;
+    }
+
+    let exec_env = exec_env(exec);
+
+    return Some(EksTokenRequest {
+        cluster_name: arg_value(&args, "--cluster-name")?,
+        region: arg_value(&args, "--region")
+            .or_else(|| exec_env.get("AWS_REGION").cloned())
+            .or_else(|| exec_env.get("AWS_DEFAULT_REGION").cloned()),
+        profile: arg_value(&args, "--profile").or_else(|| exec_env.get("AWS_PROFILE").cloned()),
+    });
+}
+
+/// Parses an ini file into `section -> key -> value`.
+fn parse_ini(contents: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            current = line[1..line.len() - 1].trim().to_string();
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    return sections;
+}
+
+fn read_ini(path: &Path) -> HashMap<String, HashMap<String, String>> {
+    return fs::read_to_string(path)
+        .map(|contents| parse_ini(&contents))
+        .unwrap_or_default();
+}
+
+fn config_section<'a>(
+    config: &'a HashMap<String, HashMap<String, String>>,
+    profile: &str,
+) -> Option<&'a HashMap<String, String>> {
+    if profile == "default" {
+        return config.get("default").or_else(|| config.get("profile default"));
+    }
+
+    return config.get(&format!("profile {}", profile));
+}
+
+fn resolve_profile(request: &EksTokenRequest) -> String {
+    return request
+        .profile
+        .clone()
+        .or_else(|| env::var("AWS_PROFILE").ok())
+        .unwrap_or_else(|| "default".to_string());
+}
+
+/// Whether `profile`, or a profile it assumes a role from, signs in through
+/// IAM Identity Center, which is the only case `aws sso login` renews.
+pub fn uses_sso(files: &AwsFiles, profile: Option<&str>) -> bool {
+    let config = read_ini(&files.config);
+    let mut profile = profile
+        .map(|profile| profile.to_string())
+        .or_else(|| env::var("AWS_PROFILE").ok())
+        .unwrap_or_else(|| "default".to_string());
+
+    for _ in 0..MAX_SOURCE_PROFILES {
+        let section = match config_section(&config, &profile) {
+            Some(section) => section,
+            None => return false,
+        };
+        if section.keys().any(|key| key.starts_with("sso_")) {
+            return true;
+        }
+        match section.get("source_profile") {
+            Some(source) => profile = source.clone(),
+            None => return false,
+        }
+    }
+
+    return false;
+}
+
+/// Resolves the region the same way the aws CLI does: explicit flag, the
+/// environment, then the profile's `region`.
+pub fn resolve_region(files: &AwsFiles, request: &EksTokenRequest) -> String {
+    let config = read_ini(&files.config);
+
+    return request
+        .region
+        .clone()
+        .or_else(|| env::var("AWS_REGION").ok())
+        .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+        .or_else(|| {
+            config_section(&config, &resolve_profile(request))
+                .and_then(|section| section.get("region").cloned())
+        })
+        .unwrap_or_else(|| "us-east-1".to_string());
+}
+
+fn expired_error(message: String) -> SerializableKubeError {
+    return SerializableKubeError::new(ErrorKind::AuthExpired, message);
+}
+
+/// Reads the access token `aws sso login` cached for a start url or sso session.
+pub fn read_sso_token(files: &AwsFiles, cache_key: &str, now: DateTime<Utc>) -> Result<String, SerializableKubeError> {
+    let file_name = format!("{}.json", hex::encode(Sha1::digest(cache_key.as_bytes())));
+    let cache_file = files.sso_cache.join(file_name);
+
+    let contents = fs::read_to_string(&cache_file)
+        .map_err(|_| expired_error(format!("No cached SSO token for {}, please log in", cache_key)))?;
+    let cached: SsoCachedToken = serde_json::from_str(&contents).map_err(|err| {
+        SerializableKubeError::new(
+            ErrorKind::ExecPluginFailed,
+            format!("Unable to parse {:?}: {}", cache_file, err),
+        )
+    })?;
+
+    // The CLI has written both RFC 3339 and "2024-01-01T00:00:00UTC" timestamps.
+    let expires_at = DateTime::parse_from_rfc3339(&cached.expires_at.replace("UTC", "Z"))
+        .map(|expires_at| expires_at.with_timezone(&Utc))
+        .map_err(|err| {
+            SerializableKubeError::new(
+                ErrorKind::ExecPluginFailed,
+                format!("Unable to parse SSO token expiry {}: {}", cached.expires_at, err),
+            )
+        })?;
+
+    if expires_at <= now {
+        return Err(expired_error(format!("The SSO session for {} has expired", cache_key)));
+    }
+
+    return Ok(cached.access_token);
+}
+
+async fn fetch_role_credentials(
+    sso_region: &str,
+    access_token: &str,
+    account_id: &str,
+    role_name: &str,
+) -> Result<AwsCredentials, SerializableKubeError> {
+    let url = format!(
+        "https://portal.sso.{}.{}/federation/credentials",
+        sso_region,
+        partition_domain(sso_region)
+    );
+
+    let response = reqwest::Client::new()
+        .get(url)
+        .query(&[("account_id", account_id), ("role_name", role_name)])
+        .header("x-amz-sso_bearer_token", access_token)
+        .send()
+        .await
+        .map_err(|err| SerializableKubeError::new(ErrorKind::ConnectionRefused, err.to_string()))?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED
+        || response.status() == reqwest::StatusCode::FORBIDDEN
+    {
+        return Err(expired_error("The SSO session has expired, please log in".to_string()));
+    }
+
+    let credentials: RoleCredentialsResponse = response
+        .error_for_status()
+        .map_err(|err| SerializableKubeError::new(ErrorKind::ExecPluginFailed, err.to_string()))?
+        .json()
+        .await
+        .map_err(|err| SerializableKubeError::new(ErrorKind::ExecPluginFailed, err.to_string()))?;
+
+    return Ok(AwsCredentials {
+        access_key_id: credentials.role_credentials.access_key_id,
+        secret_access_key: credentials.role_credentials.secret_access_key,
+        session_token: Some(credentials.role_credentials.session_token),
+    });
+}
+
+/// Resolves credentials from the environment, `~/.aws/credentials` or an SSO
+/// profile in `~/.aws/config`. Returns `None` for setups that need the aws CLI,
+/// like `credential_process` or role chaining.
+pub async fn resolve_credentials(
+    files: &AwsFiles,
+    request: &EksTokenRequest,
+    now: DateTime<Utc>,
+) -> Result<Option<AwsCredentials>, SerializableKubeError> {
+    if request.profile.is_none() && env::var("AWS_PROFILE").is_err() {
+        if let (Ok(access_key_id), Ok(secret_access_key)) =
+            (env::var("AWS_ACCESS_KEY_ID"), env::var("AWS_SECRET_ACCESS_KEY"))
+        {
+            return Ok(Some(AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token: env::var("AWS_SESSION_TOKEN").ok(),
+            }));
+        }
+    }
+
+    let profile = resolve_profile(request);
+
+    let credentials = read_ini(&files.credentials);
+    if let Some(section) = credentials.get(&profile) {
+        if let (Some(access_key_id), Some(secret_access_key)) = (
+            section.get("aws_access_key_id"),
+            section.get("aws_secret_access_key"),
+        ) {
+            return Ok(Some(AwsCredentials {
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+                session_token: section.get("aws_session_token").cloned(),
+            }));
+        }
+    }
+
+    let config = read_ini(&files.config);
+    let section = match config_section(&config, &profile) {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+
+    if section.contains_key("role_arn") || section.contains_key("credential_process") {
+        return Ok(None);
+    }
+
+    let (account_id, role_name) = match (section.get("sso_account_id"), section.get("sso_role_name")) {
+        (Some(account_id), Some(role_name)) => (account_id, role_name),
+        _ => return Ok(None),
+    };
+
+    // Newer profiles point at an [sso-session], whose name is the cache key;
+    // legacy profiles carry the start url, which is the cache key instead.
+    let (cache_key, sso_region) = match section.get("sso_session") {
+        Some(session_name) => {
+            let session = config.get(&format!("sso-session {}", session_name));
+            (
+                session_name.clone(),
+                session.and_then(|session| session.get("sso_region")).cloned(),
+            )
+        }
+        None => (
+            section.get("sso_start_url").cloned().unwrap_or_default(),
+            section.get("sso_region").cloned(),
+        ),
+    };
+
+    let sso_region = match sso_region {
+        Some(sso_region) => sso_region,
+        None => return Ok(None),
+    };
+
+    let access_token = read_sso_token(files, &cache_key, now)?;
+
+    return fetch_role_credentials(&sso_region, &access_token, account_id, role_name)
+        .await
+        .map(Some);
+}
+
+fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    return encoded;
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+
+    return mac.finalize().into_bytes().to_vec();
+}
+
+/// The DNS suffix of the partition a region belongs to; China and the
+/// isolated regions don't live under `amazonaws.com`.
+fn partition_domain(region: &str) -> &'static str {
+    if region.starts_with("cn-") {
+        return "amazonaws.com.cn";
+    }
+    if region.starts_with("us-isob-") {
+        return "sc2s.sgov.gov";
+    }
+    if region.starts_with("us-isof-") {
+        return "csp.hci.ic.gov";
+    }
+    if region.starts_with("us-iso-") {
+        return "c2s.ic.gov";
+    }
+    if region.starts_with("eu-isoe-") {
+        return "cloud.adc-e.uk";
+    }
+
+    return "amazonaws.com";
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    return [region, service, "aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), date),
+        |key, part| hmac_sha256(&key, part),
+    );
+}
+
+/// Signs a SigV4 canonical request for `scope`, `<date>/<region>/<service>/aws4_request`.
+fn signature(signing_key: &[u8], amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    return hex::encode(hmac_sha256(signing_key, &string_to_sign));
+}
+
+/// Presigns an STS `GetCallerIdentity` request with SigV4 and wraps it the way
+/// `aws eks get-token` does. The EKS authenticator replays the request and
+/// maps the returned identity to a Kubernetes user.
+pub fn presign_token(
+    credentials: &AwsCredentials,
+    cluster_name: &str,
+    region: &str,
+    now: DateTime<Utc>,
+) -> EksToken {
+    let host = format!("sts.{}.{}", region, partition_domain(region));
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/sts/aws4_request", date, region);
+
+    let mut query = vec![
+        ("Action".to_string(), "GetCallerIdentity".to_string()),
+        ("Version".to_string(), "2011-06-15".to_string()),
+        ("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
+        (
+            "X-Amz-Credential".to_string(),
+            format!("{}/{}", credentials.access_key_id, scope),
+        ),
+        ("X-Amz-Date".to_string(), amz_date.clone()),
+        ("X-Amz-Expires".to_string(), "60".to_string()),
+        ("X-Amz-SignedHeaders".to_string(), "host;x-k8s-aws-id".to_string()),
+    ];
+    if let Some(session_token) = &credentials.session_token {
+        query.push(("X-Amz-Security-Token".to_string(), session_token.clone()));
+    }
+    query.sort();
+
+    let canonical_query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", uri_encode(key), uri_encode(value)))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let canonical_request = format!(
+        "GET\n/\n{}\nhost:{}\nx-k8s-aws-id:{}\n\nhost;x-k8s-aws-id\n{}",
+        canonical_query,
+        host,
+        cluster_name,
+        hex::encode(Sha256::digest(b""))
+    );
+    let signature = signature(
+        &signing_key(&credentials.secret_access_key, &date, region, "sts"),
+        &amz_date,
+        &scope,
+        &canonical_request,
+    );
+
+    let url = format!(
+        "https://{}/?{}&X-Amz-Signature={}",
+        host, canonical_query, signature
+    );
+
+    return EksToken {
+        token: format!("{}{}", TOKEN_PREFIX, URL_SAFE_NO_PAD.encode(url)),
+        expires_at: now + Duration::minutes(TOKEN_LIFETIME_MINUTES),
+    };
+}
+
+/// Returns a cached or freshly generated token for an `aws eks get-token`
+/// exec plugin, or `None` when the aws CLI has to handle it after all. This
+/// runs for every request of a client, so cached tokens are returned without
+/// touching the aws config files.
+pub async fn token_for_exec(exec: &ExecConfig) -> Result<Option<EksToken>, SerializableKubeError> {
+    let request = match parse_exec(exec) {
+        Some(request) => request,
+        None => return Ok(None),
+    };
+
+    let now = Utc::now();
+    let cache_key = format!(
+        "{}/{}/{}",
+        request.profile.clone().unwrap_or_default(),
+        request.region.clone().unwrap_or_default(),
+        request.cluster_name
+    );
+
+    if let Some(token) = TOKENS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|tokens| tokens.get(&cache_key))
+        .filter(|token| token.expires_at - Duration::minutes(1) > now)
+    {
+        return Ok(Some(token.clone()));
+    }
+
+    let files = AwsFiles::from_env();
+    let region = resolve_region(&files, &request);
+    let credentials = match resolve_credentials(&files, &request, now).await? {
+        Some(credentials) => credentials,
+        None => return Ok(None),
+    };
+
+    let token = presign_token(&credentials, &request.cluster_name, &region, now);
+    TOKENS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(cache_key, token.clone());
+
+    return Ok(Some(token));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_files(name: &str, config: &str, credentials: &str) -> AwsFiles {
+        let dir = env::temp_dir().join(format!("jet-pilot-eks-{}-{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("sso").join("cache")).unwrap();
+        fs::write(dir.join("config"), config).unwrap();
+        fs::write(dir.join("credentials"), credentials).unwrap();
+
+        return AwsFiles {
+            config: dir.join("config"),
+            credentials: dir.join("credentials"),
+            sso_cache: dir.join("sso").join("cache"),
+        };
+    }
+
+    fn request(profile: &str) -> EksTokenRequest {
+        return EksTokenRequest {
+            cluster_name: "demo".to_string(),
+            region: Some("eu-west-1".to_string()),
+            profile: Some(profile.to_string()),
+        };
+    }
+
+    fn now() -> DateTime<Utc> {
+        return DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z").unwrap().with_timezone(&Utc);
+    }
+
+    #[test]
+    fn uses_sso_follows_source_profiles() {
+        let files = fixture_files(
+            "uses-sso",
+            "[profile sso]\nsso_session = corp\nsso_account_id = 1\n\n[profile legacy]\nsso_start_url = https://corp.awsapps.com/start\n\n[profile assumed]\nrole_arn = arn:aws:iam::1:role/admin\nsource_profile = sso\n\n[profile keys]\nregion = eu-west-1\n\n[profile loop]\nsource_profile = loop\n",
+            "[keys]\naws_access_key_id = AKID\naws_secret_access_key = secret\n",
+        );
+
+        assert!(uses_sso(&files, Some("sso")));
+        assert!(uses_sso(&files, Some("legacy")));
+        assert!(uses_sso(&files, Some("assumed")));
+        assert!(!uses_sso(&files, Some("keys")));
+        assert!(!uses_sso(&files, Some("missing")));
+        assert!(!uses_sso(&files, Some("loop")));
+    }
+
+    #[test]
+    fn parse_ini_reads_sections_and_skips_comments() {
+        let ini = parse_ini(
+            "# comment\n[default]\nregion = eu-west-1\n\n; other comment\n[profile dev]\n  aws_access_key_id=AKID \nsso_start_url = https://example.awsapps.com/start?a=b\n",
+        );
+
+        assert_eq!(ini["default"]["region"], "eu-west-1");
+        assert_eq!(ini["profile dev"]["aws_access_key_id"], "AKID");
+        assert_eq!(ini["profile dev"]["sso_start_url"], "https://example.awsapps.com/start?a=b");
+    }
+
+    #[test]
+    fn parse_exec_leaves_role_assumption_to_the_cli() {
+        let exec = |args: &[&str]| -> ExecConfig {
+            serde_json::from_value(serde_json::json!({
+                "command": "/usr/local/bin/aws",
+                "args": args,
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            parse_exec(&exec(&["--region", "eu-west-1", "eks", "get-token", "--cluster-name", "demo"])),
+            Some(EksTokenRequest {
+                cluster_name: "demo".to_string(),
+                region: Some("eu-west-1".to_string()),
+                profile: None,
+            })
+        );
+        assert_eq!(
+            parse_exec(&exec(&["eks", "get-token", "--cluster-name", "demo", "--role-arn", "arn:aws:iam::1:role/x"])),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn credentials_file_profile_is_used() {
+        let files = fixture_files(
+            "credentials",
+            "",
+            "[dev]\naws_access_key_id = AKID\naws_secret_access_key = SECRET\naws_session_token = SESSION\n",
+        );
+
+        let credentials = resolve_credentials(&files, &request("dev"), now()).await.unwrap();
+
+        assert_eq!(
+            credentials,
+            Some(AwsCredentials {
+                access_key_id: "AKID".to_string(),
+                secret_access_key: "SECRET".to_string(),
+                session_token: Some("SESSION".to_string()),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn source_profile_and_credential_process_are_left_to_the_cli() {
+        let files = fixture_files(
+            "source-profile",
+            "[profile assumed]\nrole_arn = arn:aws:iam::1:role/admin\nsource_profile = dev\n\n[profile process]\ncredential_process = /bin/creds\n",
+            "[dev]\naws_access_key_id = AKID\naws_secret_access_key = SECRET\n",
+        );
+
+        assert_eq!(resolve_credentials(&files, &request("assumed"), now()).await.unwrap(), None);
+        assert_eq!(resolve_credentials(&files, &request("process"), now()).await.unwrap(), None);
+        assert_eq!(resolve_credentials(&files, &request("missing"), now()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_sso_session_asks_for_a_login() {
+        let files = fixture_files(
+            "sso-expired",
+            "[profile sso]\nsso_session = corp\nsso_account_id = 111111111111\nsso_role_name = Admin\n\n[sso-session corp]\nsso_region = eu-west-1\n",
+            "",
+        );
+        fs::write(
+            files.sso_cache.join(format!("{}.json", hex::encode(Sha1::digest(b"corp")))),
+            r#"{"accessToken": "token", "expiresAt": "2024-01-01T11:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let error = resolve_credentials(&files, &request("sso"), now()).await.unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::AuthExpired);
+    }
+
+    #[test]
+    fn read_sso_token_accepts_legacy_timestamps() {
+        let start_url = "https://example.awsapps.com/start";
+        let files = fixture_files("sso-legacy", "", "");
+        fs::write(
+            files.sso_cache.join(format!("{}.json", hex::encode(Sha1::digest(start_url.as_bytes())))),
+            r#"{"accessToken": "token", "expiresAt": "2024-01-01T13:00:00UTC"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(read_sso_token(&files, start_url, now()).unwrap(), "token");
+        assert_eq!(
+            read_sso_token(&files, "https://other.awsapps.com/start", now()).unwrap_err().kind,
+            ErrorKind::AuthExpired
+        );
+    }
+
+    // Known answers from the AWS documentation on deriving a SigV4 signing
+    // key and on signing the IAM `ListUsers` example request.
+    #[test]
+    fn signing_key_matches_aws_example() {
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn signature_matches_aws_example() {
+        let canonical_request = "GET\n/\nAction=ListUsers&Version=2010-05-08\ncontent-type:application/x-www-form-urlencoded; charset=utf-8\nhost:iam.amazonaws.com\nx-amz-date:20150830T123600Z\n\ncontent-type;host;x-amz-date\ne3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20150830", "us-east-1", "iam");
+
+        assert_eq!(
+            signature(&key, "20150830T123600Z", "20150830/us-east-1/iam/aws4_request", canonical_request),
+            "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn presign_token_uses_the_partition_endpoint() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKID".to_string(),
+            secret_access_key: "SECRET".to_string(),
+            session_token: Some("a/b+c".to_string()),
+        };
+
+        let token = presign_token(&credentials, "demo", "cn-north-1", now());
+        let url = String::from_utf8(
+            URL_SAFE_NO_PAD
+                .decode(token.token.strip_prefix(TOKEN_PREFIX).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert!(url.starts_with("https://sts.cn-north-1.amazonaws.com.cn/?Action=GetCallerIdentity&Version=2011-06-15&"));
+        assert!(url.contains("X-Amz-Credential=AKID%2F20240101%2Fcn-north-1%2Fsts%2Faws4_request"));
+        assert!(url.contains("X-Amz-Security-Token=a%2Fb%2Bc"));
+        assert!(url.contains("X-Amz-SignedHeaders=host%3Bx-k8s-aws-id"));
+        assert_eq!(token.expires_at, now() + Duration::minutes(14));
+
+        let token = presign_token(&credentials, "demo", "us-gov-west-1", now());
+        let url = String::from_utf8(
+            URL_SAFE_NO_PAD
+                .decode(token.token.strip_prefix(TOKEN_PREFIX).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(url.starts_with("https://sts.us-gov-west-1.amazonaws.com/"));
+    }
+}
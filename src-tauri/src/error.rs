@@ -0,0 +1,520 @@
+use kube::client::AuthError;
+use kube::config::KubeconfigError;
+use kube::core::ErrorResponse;
+use kube::runtime::watcher;
+use kube::Error;
+use regex::Regex;
+use serde::Serialize;
+use std::io;
+
+/// Lets the UI recognise an error without matching on its message.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum ErrorKind {
+    Unknown,
+    SettingsMissing,
+    SettingsInvalid,
+    KubeconfigNotFound,
+    KubeconfigInvalid,
+    NoCurrentContext,
+    ContextNotFound,
+    AuthExpired,
+    Forbidden,
+    NotFound,
+    Conflict,
+    Invalid,
+    ConnectionRefused,
+    Tls,
+    Timeout,
+    ExecPluginFailed,
+    DiscoveryFailed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SerializableKubeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub code: Option<u16>,
+    pub reason: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl SerializableKubeError {
+    pub fn new(kind: ErrorKind, message: String) -> Self {
+        return SerializableKubeError {
+            kind,
+            message,
+            code: None,
+            reason: None,
+            details: None,
+        };
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        return self;
+    }
+}
+
+impl std::fmt::Display for SerializableKubeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+/// Lets client middleware fail requests with an already classified error,
+/// which `From<Error>` unwraps again.
+impl std::error::Error for SerializableKubeError {}
+
+/// Phrases exec plugins print when the user has to log in again, as opposed
+/// to the plugin itself being broken or missing.
+const EXPIRED_CREDENTIAL_HINTS: [&str; 12] = [
+    "error loading sso token",
+    "token has expired",
+    "token is expired",
+    "sso session",
+    "refresh token",
+    "reauthenticate",
+    "reauthentication",
+    "login again",
+    "gcloud auth login",
+    "az login",
+    "invalid_grant",
+    "aadsts",
+];
+
+fn kind_for_status(code: u16, reason: &str) -> ErrorKind {
+    match (code, reason) {
+        (401, _) => ErrorKind::AuthExpired,
+        (403, _) => ErrorKind::Forbidden,
+        (404, _) => ErrorKind::NotFound,
+        (409, _) | (_, "Conflict") | (_, "AlreadyExists") => ErrorKind::Conflict,
+        (422, _) | (_, "Invalid") => ErrorKind::Invalid,
+        (408, _) | (504, _) | (_, "Timeout") => ErrorKind::Timeout,
+        _ => ErrorKind::Unknown,
+    }
+}
+
+fn looks_like_field_path(segment: &str) -> bool {
+    match segment.split_once(": ") {
+        Some((field, _)) => {
+            !field.is_empty()
+                && field
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "._-[]/'\"".contains(c))
+        }
+        None => false,
+    }
+}
+
+/// Parses the conflicts of a rejected server-side apply, which the api server
+/// reports as `conflict with "<manager>" [using <apiVersion>]: <field>` or, for
+/// several fields, one `conflicts with "<manager>" [using <apiVersion>]:` line
+/// per manager followed by a `- <field>` line per field.
+fn apply_conflicts(message: &str) -> Option<Vec<serde_json::Value>> {
+    let conflicts = message.strip_prefix("Apply failed with ")?;
+    let (_, conflicts) = conflicts.split_once(": ")?;
+    let manager_pattern = Regex::new(r#"(?m)^conflicts? with "([^"]*)"(?: using [^:\s]+)?:"#).unwrap();
+
+    let managers: Vec<_> = manager_pattern.captures_iter(conflicts).collect();
+    let mut result: Vec<serde_json::Value> = Vec::new();
+    for (index, captures) in managers.iter().enumerate() {
+        let manager = &captures[1];
+        let start = captures.get(0).unwrap().end();
+        let end = managers
+            .get(index + 1)
+            .map(|next| next.get(0).unwrap().start())
+            .unwrap_or(conflicts.len());
+
+        for field in conflicts[start..end].split('\n') {
+            let field = field.trim().trim_start_matches("- ").trim();
+            if field.is_empty() {
+                continue;
+            }
+            result.push(serde_json::json!({
+                "manager": manager,
+                "field": field,
+            }));
+        }
+    }
+
+    return Some(result);
+}
+
+/// Rebuilds the `Status.details` of an api error from its message.
+///
+/// `ErrorResponse` only keeps status, message, reason and code, but the api
+/// server formats messages as `<kind> "<name>" <problem>` and, for invalid
+/// objects, appends the causes as `field: message` pairs.
+fn status_details(error: &ErrorResponse) -> Option<serde_json::Value> {
+    if let Some(conflicts) = apply_conflicts(&error.message) {
+        return Some(serde_json::json!({ "conflicts": conflicts }));
+    }
+
+    let (target, problem) = match error.message.split_once("\" ") {
+        Some((target, problem)) => (Some(target), problem),
+        None => (None, error.message.as_str()),
+    };
+    let (kind, name) = match target.and_then(|target| target.split_once(" \"")) {
+        Some((kind, name)) => (Some(kind.to_string()), Some(name.to_string())),
+        None => (None, None),
+    };
+
+    let mut causes: Vec<serde_json::Value> = Vec::new();
+    if let Some((_, invalid)) = problem.split_once("is invalid: ") {
+        let invalid = invalid.trim().trim_start_matches('[').trim_end_matches(']');
+
+        let mut segments: Vec<String> = Vec::new();
+        for segment in invalid.split(", ") {
+            match segments.last_mut() {
+                Some(last) if !looks_like_field_path(segment) => {
+                    last.push_str(", ");
+                    last.push_str(segment);
+                }
+                _ => segments.push(segment.to_string()),
+            }
+        }
+
+        causes = segments
+            .iter()
+            .map(|segment| match segment.split_once(": ") {
+                Some((field, message)) => serde_json::json!({
+                    "field": field,
+                    "message": message,
+                }),
+                None => serde_json::json!({ "message": segment }),
+            })
+            .collect();
+    }
+
+    if kind.is_none() && causes.is_empty() {
+        return None;
+    }
+
+    return Some(serde_json::json!({
+        "kind": kind,
+        "name": name,
+        "causes": causes,
+    }));
+}
+
+fn from_error_response(error: ErrorResponse) -> SerializableKubeError {
+    return SerializableKubeError {
+        kind: kind_for_status(error.code, &error.reason),
+        details: status_details(&error),
+        message: error.message,
+        code: Option::from(error.code),
+        reason: Option::from(error.reason),
+    };
+}
+
+/// The errors behind `error`, outermost first. The tls connector reports
+/// handshake failures as an `io::Error` around a `rustls::Error`, and
+/// `io::Error::source()` skips the error it wraps, so the chain continues
+/// into it.
+fn error_chain<'a>(error: &'a (dyn std::error::Error + 'static)) -> Vec<&'a (dyn std::error::Error + 'static)> {
+    let mut chain = Vec::new();
+    let mut current = Some(error);
+
+    while let Some(error) = current {
+        chain.push(error);
+        current = match error.downcast_ref::<io::Error>().and_then(|io_error| io_error.get_ref()) {
+            Some(inner) => Some(inner as &(dyn std::error::Error + 'static)),
+            None => error.source(),
+        };
+    }
+
+    return chain;
+}
+
+/// Classifies transport errors by walking the source chain, which works
+/// regardless of which layer (hyper, tower, the tls connector) produced them.
+/// The types of the errors decide before their messages do, since hyper
+/// describes a failed handshake as "error trying to connect" too.
+fn kind_for_transport_error(error: &(dyn std::error::Error + 'static)) -> ErrorKind {
+    let chain = error_chain(error);
+
+    for error in chain.iter() {
+        if error.is::<rustls::Error>() {
+            return ErrorKind::Tls;
+        }
+
+        if let Some(io_error) = error.downcast_ref::<io::Error>() {
+            match io_error.kind() {
+                io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::AddrNotAvailable => return ErrorKind::ConnectionRefused,
+                io::ErrorKind::TimedOut => return ErrorKind::Timeout,
+                _ => {}
+            }
+        }
+    }
+
+    for error in chain.iter() {
+        let message = error.to_string().to_lowercase();
+        if message.contains("timed out") || message.contains("deadline has elapsed") {
+            return ErrorKind::Timeout;
+        }
+        if message.contains("connection refused")
+            || message.contains("error trying to connect")
+            || message.contains("dns error")
+        {
+            return ErrorKind::ConnectionRefused;
+        }
+    }
+
+    return ErrorKind::Unknown;
+}
+
+fn from_auth_error(error: AuthError) -> SerializableKubeError {
+    match error {
+        AuthError::AuthExecRun { cmd, status, out } => {
+            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            let lowercase_stderr = stderr.to_lowercase();
+            let kind = if EXPIRED_CREDENTIAL_HINTS
+                .iter()
+                .any(|hint| lowercase_stderr.contains(hint))
+            {
+                ErrorKind::AuthExpired
+            } else {
+                ErrorKind::ExecPluginFailed
+            };
+
+            SerializableKubeError::new(
+                kind,
+                format!("Authentication command '{}' failed: {}", cmd, stderr),
+            )
+            .with_details(serde_json::json!({
+                "command": cmd,
+                "exitCode": status.code(),
+                "stderr": stderr,
+            }))
+        }
+        AuthError::AuthExecStart(_)
+        | AuthError::AuthExecParse(_)
+        | AuthError::AuthExec(_)
+        | AuthError::ExecPluginFailed => {
+            SerializableKubeError::new(ErrorKind::ExecPluginFailed, error.to_string())
+        }
+        AuthError::UnrefreshableTokenResponse | AuthError::ReadTokenFile(_, _) => {
+            SerializableKubeError::new(ErrorKind::AuthExpired, error.to_string())
+        }
+        _ => SerializableKubeError::new(ErrorKind::Unknown, error.to_string()),
+    }
+}
+
+impl From<Error> for SerializableKubeError {
+    fn from(error: Error) -> Self {
+        println!("Error: {:?}", error);
+
+        match error {
+            Error::Api(api_error) => from_error_response(api_error),
+            Error::Auth(auth_error) => from_auth_error(auth_error),
+            Error::Discovery(_) => {
+                SerializableKubeError::new(ErrorKind::DiscoveryFailed, error.to_string())
+            }
+            Error::RustlsTls(_) | Error::TlsRequired => {
+                SerializableKubeError::new(ErrorKind::Tls, error.to_string())
+            }
+            Error::InferConfig(_) => {
+                SerializableKubeError::new(ErrorKind::KubeconfigInvalid, error.to_string())
+            }
+            Error::Service(ref service_error) if service_error.is::<SerializableKubeError>() => service_error
+                .downcast_ref::<SerializableKubeError>()
+                .unwrap()
+                .clone(),
+            _ => SerializableKubeError::new(kind_for_transport_error(&error), error.to_string()),
+        }
+    }
+}
+
+impl From<KubeconfigError> for SerializableKubeError {
+    fn from(error: KubeconfigError) -> Self {
+        return SerializableKubeError::new(ErrorKind::KubeconfigInvalid, error.to_string());
+    }
+}
+
+impl From<watcher::Error> for SerializableKubeError {
+    fn from(error: watcher::Error) -> Self {
+        match error {
+            watcher::Error::InitialListFailed(err)
+            | watcher::Error::WatchStartFailed(err)
+            | watcher::Error::WatchFailed(err) => SerializableKubeError::from(err),
+            watcher::Error::WatchError(api_error) => from_error_response(api_error),
+            _ => SerializableKubeError::new(ErrorKind::Unknown, error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_response(code: u16, reason: &str, message: &str) -> ErrorResponse {
+        return ErrorResponse {
+            status: "Failure".to_string(),
+            message: message.to_string(),
+            reason: reason.to_string(),
+            code,
+        };
+    }
+
+    #[test]
+    fn not_found_keeps_kind_and_name() {
+        let error = from_error_response(error_response(
+            404,
+            "NotFound",
+            "deployments.apps \"web\" not found",
+        ));
+
+        assert_eq!(error.kind, ErrorKind::NotFound);
+        assert_eq!(
+            error.details,
+            Some(serde_json::json!({ "kind": "deployments.apps", "name": "web", "causes": [] }))
+        );
+    }
+
+    #[test]
+    fn invalid_object_causes_are_split_per_field() {
+        let error = from_error_response(error_response(
+            422,
+            "Invalid",
+            "Deployment.apps \"web\" is invalid: [spec.replicas: Invalid value: -1: must be greater than or equal to 0, \
+             spec.template.spec.containers[0].imagePullPolicy: Unsupported value: \"Sometimes\": supported values: \"Always\", \"IfNotPresent\", \"Never\", \
+             spec.template.spec.containers[0].image: Required value]",
+        ));
+
+        assert_eq!(error.kind, ErrorKind::Invalid);
+        assert_eq!(
+            error.details,
+            Some(serde_json::json!({
+                "kind": "Deployment.apps",
+                "name": "web",
+                "causes": [
+                    {
+                        "field": "spec.replicas",
+                        "message": "Invalid value: -1: must be greater than or equal to 0",
+                    },
+                    {
+                        "field": "spec.template.spec.containers[0].imagePullPolicy",
+                        "message": "Unsupported value: \"Sometimes\": supported values: \"Always\", \"IfNotPresent\", \"Never\"",
+                    },
+                    {
+                        "field": "spec.template.spec.containers[0].image",
+                        "message": "Required value",
+                    },
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn single_invalid_cause_without_brackets() {
+        let error = from_error_response(error_response(
+            422,
+            "Invalid",
+            "ConfigMap \"settings\" is invalid: metadata.name: Invalid value: \"Settings\": a lowercase RFC 1123 subdomain must consist of lower case alphanumeric characters, '-' or '.'",
+        ));
+
+        let causes = &error.details.unwrap()["causes"];
+        assert_eq!(causes.as_array().unwrap().len(), 1);
+        assert_eq!(causes[0]["field"], "metadata.name");
+    }
+
+    #[test]
+    fn apply_conflict_with_one_field() {
+        let error = from_error_response(error_response(
+            409,
+            "Conflict",
+            "Apply failed with 1 conflict: conflict with \"kubectl-client-side-apply\" using apps/v1: .spec.replicas",
+        ));
+
+        assert_eq!(error.kind, ErrorKind::Conflict);
+        assert_eq!(
+            error.details,
+            Some(serde_json::json!({
+                "conflicts": [{ "manager": "kubectl-client-side-apply", "field": ".spec.replicas" }],
+            }))
+        );
+    }
+
+    #[test]
+    fn apply_conflicts_with_several_managers() {
+        let error = from_error_response(error_response(
+            409,
+            "Conflict",
+            "Apply failed with 3 conflicts: conflicts with \"helm\" using apps/v1:\n\
+             - .spec.replicas\n\
+             - .spec.template.spec.containers[name=\"web\"].image\n\
+             conflicts with \"kube-controller-manager\" using apps/v1:\n\
+             - .metadata.annotations.deployment.kubernetes.io/revision",
+        ));
+
+        assert_eq!(
+            error.details,
+            Some(serde_json::json!({
+                "conflicts": [
+                    { "manager": "helm", "field": ".spec.replicas" },
+                    { "manager": "helm", "field": ".spec.template.spec.containers[name=\"web\"].image" },
+                    {
+                        "manager": "kube-controller-manager",
+                        "field": ".metadata.annotations.deployment.kubernetes.io/revision",
+                    },
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn apply_conflicts_keep_fields_and_managers_that_mention_conflict() {
+        let error = from_error_response(error_response(
+            409,
+            "Conflict",
+            "Apply failed with 2 conflicts: conflicts with \"conflict-resolver\" using v1:\n\
+             - .data.conflict\n\
+             - .metadata.labels.conflicts with\n",
+        ));
+
+        assert_eq!(
+            error.details,
+            Some(serde_json::json!({
+                "conflicts": [
+                    { "manager": "conflict-resolver", "field": ".data.conflict" },
+                    { "manager": "conflict-resolver", "field": ".metadata.labels.conflicts with" },
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn tls_errors_are_recognised_by_type() {
+        let handshake = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownIssuer),
+        );
+        assert_eq!(kind_for_transport_error(&handshake), ErrorKind::Tls);
+
+        let mentions_tls = io::Error::other("failed to read tls-ca.pem");
+        assert_eq!(kind_for_transport_error(&mentions_tls), ErrorKind::Unknown);
+    }
+
+    #[test]
+    fn connection_errors_keep_their_io_kind() {
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "connect failed");
+        assert_eq!(kind_for_transport_error(&refused), ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn messages_without_a_target_have_no_details() {
+        let error = from_error_response(error_response(
+            500,
+            "InternalError",
+            "Internal error occurred: failed calling webhook",
+        ));
+
+        assert_eq!(error.kind, ErrorKind::Unknown);
+        assert_eq!(error.details, None);
+    }
+}
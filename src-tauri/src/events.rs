@@ -0,0 +1,342 @@
+use crate::watch::{namespaced_or_all, register_watch, WatchEvent};
+use crate::{client_with_context, SerializableKubeError};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::ObjectReference;
+use k8s_openapi::api::events::v1::Event;
+use kube::api::{Api, ListParams};
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::ResourceExt;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tauri::Manager;
+
+/// One line of an event timeline. Events the api server records separately
+/// for the same occurrence, like a series and its repeats, are folded into a
+/// single summary with the combined count.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSummary {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub reason: Option<String>,
+    pub note: Option<String>,
+    pub regarding: Option<ObjectReference>,
+    pub reporting_controller: Option<String>,
+    pub count: i32,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// Builds the field selector for events about a single object. Empty values
+/// are left out, so passing nothing selects every event in the namespace.
+fn regarding_selector(kind: &str, name: &str, uid: &str) -> String {
+    return [("regarding.kind", kind), ("regarding.name", name), ("regarding.uid", uid)]
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(field, value)| format!("{}={}", field, value))
+        .collect::<Vec<String>>()
+        .join(",");
+}
+
+fn summary_key(event: &Event) -> String {
+    let regarding = event.regarding.clone().unwrap_or_default();
+
+    return format!(
+        "{}/{}/{}/{}/{}/{}",
+        regarding.uid.or(regarding.name).unwrap_or_default(),
+        regarding.kind.unwrap_or_default(),
+        regarding.field_path.unwrap_or_default(),
+        event.type_.clone().unwrap_or_default(),
+        event.reason.clone().unwrap_or_default(),
+        event.note.clone().unwrap_or_default(),
+    );
+}
+
+fn event_count(event: &Event) -> i32 {
+    return event
+        .series
+        .as_ref()
+        .map(|series| series.count)
+        .or(event.deprecated_count)
+        .unwrap_or(1)
+        .max(1);
+}
+
+fn first_seen(event: &Event) -> Option<DateTime<Utc>> {
+    return event
+        .deprecated_first_timestamp
+        .as_ref()
+        .map(|time| time.0)
+        .or(event.event_time.as_ref().map(|time| time.0))
+        .or(event.metadata.creation_timestamp.as_ref().map(|time| time.0));
+}
+
+fn last_seen(event: &Event) -> Option<DateTime<Utc>> {
+    return event
+        .series
+        .as_ref()
+        .map(|series| series.last_observed_time.0)
+        .or(event.deprecated_last_timestamp.as_ref().map(|time| time.0))
+        .or(event.event_time.as_ref().map(|time| time.0))
+        .or(event.metadata.creation_timestamp.as_ref().map(|time| time.0));
+}
+
+/// Folds events that share a key into one summary.
+fn summarize<'a>(key: &str, events: impl Iterator<Item = &'a Event>) -> Option<EventSummary> {
+    let mut summary: Option<EventSummary> = None;
+
+    for event in events {
+        let summary = summary.get_or_insert_with(|| EventSummary {
+            key: key.to_string(),
+            type_: event.type_.clone(),
+            reason: event.reason.clone(),
+            note: event.note.clone(),
+            regarding: event.regarding.clone(),
+            reporting_controller: event.reporting_controller.clone(),
+            count: 0,
+            first_seen: None,
+            last_seen: None,
+        });
+
+        summary.count += event_count(event);
+        summary.first_seen = match (summary.first_seen, first_seen(event)) {
+            (Some(current), Some(seen)) => Some(current.min(seen)),
+            (current, seen) => current.or(seen),
+        };
+        summary.last_seen = summary.last_seen.max(last_seen(event));
+    }
+
+    return summary;
+}
+
+fn summarize_all<'a>(events: impl Iterator<Item = &'a Event>) -> Vec<EventSummary> {
+    let mut grouped: HashMap<String, Vec<&Event>> = HashMap::new();
+    for event in events {
+        grouped.entry(summary_key(event)).or_default().push(event);
+    }
+
+    let mut summaries: Vec<EventSummary> = grouped
+        .iter()
+        .filter_map(|(key, events)| summarize(key, events.iter().copied()))
+        .collect();
+    summaries.sort_by_key(|summary| summary.last_seen);
+
+    return summaries;
+}
+
+fn event_id(event: &Event) -> String {
+    return event.uid().unwrap_or_else(|| event.name_any());
+}
+
+/// The events of a watch, indexed by summary key so folding a change only
+/// looks at the events of the summary it touches.
+#[derive(Default)]
+struct EventIndex {
+    events: HashMap<String, Event>,
+    keys: HashMap<String, HashSet<String>>,
+}
+
+impl EventIndex {
+    /// Stores `event`, returning the summary keys it was removed from or
+    /// added to.
+    fn apply(&mut self, event: Event) -> Vec<String> {
+        let id = event_id(&event);
+        let key = summary_key(&event);
+
+        let mut changed: Vec<String> = self.remove(&id).into_iter().filter(|previous| *previous != key).collect();
+        self.keys.entry(key.clone()).or_default().insert(id.clone());
+        self.events.insert(id, event);
+        changed.push(key);
+
+        return changed;
+    }
+
+    /// Forgets the event with `id`, returning the summary key it belonged to.
+    fn remove(&mut self, id: &str) -> Option<String> {
+        let event = self.events.remove(id)?;
+        let key = summary_key(&event);
+
+        if let Some(ids) = self.keys.get_mut(&key) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.keys.remove(&key);
+            }
+        }
+
+        return Some(key);
+    }
+
+    fn summarize(&self, key: &str) -> Option<EventSummary> {
+        let ids = self.keys.get(key)?;
+        return summarize(key, ids.iter().filter_map(|id| self.events.get(id)));
+    }
+}
+
+/// Lists the events of a namespace, or of a single object when `kind`, `name`
+/// or `uid` are given, as a timeline ordered by when they were last seen.
+#[tauri::command]
+pub async fn list_events(
+    context: &str,
+    namespace: &str,
+    kind: &str,
+    name: &str,
+    uid: &str,
+) -> Result<Vec<EventSummary>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let event_api: Api<Event> = namespaced_or_all(client, namespace);
+
+    let mut list_params = ListParams::default();
+    let selector = regarding_selector(kind, name, uid);
+    if !selector.is_empty() {
+        list_params = list_params.fields(&selector);
+    }
+
+    let events = event_api
+        .list(&list_params)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    return Ok(summarize_all(events.items.iter()));
+}
+
+/// Emits `watch_event_<subscription_id>` events with `EventSummary` objects.
+/// A summary is `MODIFIED` whenever another occurrence is folded into it, and
+/// `DELETED` once the api server expired all of its events.
+#[tauri::command]
+pub async fn watch_events(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    kind: &str,
+    name: &str,
+    uid: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let event_api: Api<Event> = namespaced_or_all(client, namespace);
+
+    let mut config = watcher::Config::default();
+    let selector = regarding_selector(kind, name, uid);
+    if !selector.is_empty() {
+        config = config.fields(&selector);
+    }
+
+    let event_name = format!("watch_event_{}", subscription_id);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut index = EventIndex::default();
+        let mut summaries: HashMap<String, EventSummary> = HashMap::new();
+        let mut stream = watcher(event_api, config).default_backoff().boxed();
+
+        while let Some(change) = stream.next().await {
+            let changed_keys: Vec<String> = match change {
+                Ok(watcher::Event::Applied(event)) => index.apply(event),
+                Ok(watcher::Event::Deleted(event)) => {
+                    index.remove(&event_id(&event));
+                    vec![summary_key(&event)]
+                }
+                Ok(watcher::Event::Restarted(relisted)) => {
+                    index = EventIndex::default();
+                    for event in relisted {
+                        index.apply(event);
+                    }
+
+                    let mut keys: Vec<String> = summaries.keys().cloned().collect();
+                    keys.extend(index.keys.keys().cloned());
+                    keys.sort();
+                    keys.dedup();
+                    keys
+                }
+                Err(err) => {
+                    let error: WatchEvent<EventSummary> = WatchEvent::Error(SerializableKubeError::from(err));
+                    if app_handle.emit_all(&event_name, error).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            for key in changed_keys {
+                let summary = index.summarize(&key);
+
+                let update = match (summaries.get(&key), summary) {
+                    (None, Some(summary)) => Some(WatchEvent::Added(summary)),
+                    (Some(previous), Some(summary)) if *previous != summary => {
+                        Some(WatchEvent::Modified(summary))
+                    }
+                    (Some(previous), None) => Some(WatchEvent::Deleted(previous.clone())),
+                    _ => None,
+                };
+
+                match &update {
+                    Some(WatchEvent::Added(summary)) | Some(WatchEvent::Modified(summary)) => {
+                        summaries.insert(key.clone(), summary.clone());
+                    }
+                    Some(WatchEvent::Deleted(_)) => {
+                        summaries.remove(&key);
+                    }
+                    _ => {}
+                }
+
+                if let Some(update) = update {
+                    if app_handle.emit_all(&event_name, update).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    register_watch(subscription_id, handle);
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(uid: &str, reason: &str, count: i32) -> Event {
+        return serde_json::from_value(serde_json::json!({
+            "metadata": { "name": uid, "uid": uid },
+            "eventTime": "2024-01-01T00:00:00.000000Z",
+            "regarding": { "kind": "Pod", "name": "web-0", "uid": "pod-uid" },
+            "type": "Warning",
+            "reason": reason,
+            "note": "Back-off restarting failed container",
+            "deprecatedCount": count,
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn index_folds_events_by_summary_key() {
+        let mut index = EventIndex::default();
+        let first = event("a", "BackOff", 2);
+        let key = summary_key(&first);
+
+        assert_eq!(index.apply(first), vec![key.clone()]);
+        assert_eq!(index.apply(event("b", "BackOff", 3)), vec![key.clone()]);
+        assert_eq!(index.summarize(&key).unwrap().count, 5);
+
+        assert_eq!(index.remove("a"), Some(key.clone()));
+        assert_eq!(index.summarize(&key).unwrap().count, 3);
+        assert_eq!(index.remove("b"), Some(key.clone()));
+        assert!(index.summarize(&key).is_none());
+        assert!(index.keys.is_empty());
+    }
+
+    #[test]
+    fn index_moves_events_whose_key_changed() {
+        let mut index = EventIndex::default();
+        let before = event("a", "BackOff", 1);
+        let after = event("a", "Failed", 1);
+        let (old_key, new_key) = (summary_key(&before), summary_key(&after));
+
+        index.apply(before);
+        assert_eq!(index.apply(after), vec![old_key.clone(), new_key.clone()]);
+        assert!(index.summarize(&old_key).is_none());
+        assert_eq!(index.summarize(&new_key).unwrap().reason.as_deref(), Some("Failed"));
+    }
+}
@@ -0,0 +1,442 @@
+use crate::client_pool::invalidate_client;
+use crate::dynamic::invalidate_api_resources;
+use crate::{ErrorKind, SerializableKubeError};
+use kube::config::{ExecConfig, Kubeconfig, KubeconfigError, NamedAuthInfo};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::api::path;
+use tauri::Manager;
+
+static SETTINGS_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// A kubeconfig merged from several files, remembering where each context was defined.
+pub struct MergedKubeconfig {
+    pub kubeconfig: Kubeconfig,
+    pub context_sources: HashMap<String, PathBuf>,
+}
+
+#[derive(Serialize)]
+pub struct ContextSource {
+    name: String,
+    cluster: Option<String>,
+    user: Option<String>,
+    namespace: Option<String>,
+    source: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct KubeconfigChangedPayload {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+}
+
+pub fn set_settings_file(settings_file: PathBuf) {
+    let _ = SETTINGS_FILE.set(settings_file);
+}
+
+pub fn read_settings() -> Result<serde_json::Value, SerializableKubeError> {
+    let settings_file = SETTINGS_FILE.get().ok_or(SerializableKubeError::new(
+        ErrorKind::SettingsMissing,
+        "Unable to determine the location of settings.json".to_string(),
+    ))?;
+    let file = settings_file.to_string_lossy().to_string();
+
+    let json_string = fs::read_to_string(settings_file).map_err(|err| {
+        SerializableKubeError::new(
+            ErrorKind::SettingsMissing,
+            format!("Unable to load settings from {}: {}", file, err),
+        )
+        .with_details(serde_json::json!({ "file": file }))
+    })?;
+
+    return serde_json::from_str(&json_string).map_err(|err| {
+        SerializableKubeError::new(
+            ErrorKind::SettingsInvalid,
+            format!("Unable to parse settings from {}: {}", file, err),
+        )
+        .with_details(serde_json::json!({
+            "file": file,
+            "line": err.line(),
+            "column": err.column(),
+        }))
+    });
+}
+
+fn kubeconfig_error(path: &Path, error: KubeconfigError) -> SerializableKubeError {
+    let file = path.to_string_lossy().to_string();
+
+    match error {
+        KubeconfigError::ReadConfig(err, _) if err.kind() == io::ErrorKind::NotFound => {
+            SerializableKubeError::new(
+                ErrorKind::KubeconfigNotFound,
+                format!("Kubeconfig {} not found", file),
+            )
+            .with_details(serde_json::json!({ "file": file }))
+        }
+        KubeconfigError::Parse(err) | KubeconfigError::InvalidStructure(err) => {
+            let location = err.location();
+            SerializableKubeError::new(
+                ErrorKind::KubeconfigInvalid,
+                format!("Unable to parse kubeconfig {}: {}", file, err),
+            )
+            .with_details(serde_json::json!({
+                "file": file,
+                "line": location.as_ref().map(|location| location.line()),
+                "column": location.as_ref().map(|location| location.column()),
+            }))
+        }
+        _ => SerializableKubeError::new(
+            ErrorKind::KubeconfigInvalid,
+            format!("Unable to load kubeconfig {}: {}", file, error),
+        )
+        .with_details(serde_json::json!({ "file": file })),
+    }
+}
+
+fn expand_home(value: &str) -> String {
+    match (value.strip_prefix("~/"), path::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Returns the kubeconfig files to load, in merge order.
+///
+/// Follows `KUBECONFIG` semantics: `client.currentKubeConfig` from the settings,
+/// or else the `KUBECONFIG` environment variable, is split on the platform's
+/// path separator and falls back to `~/.kube/config`. Files matching the
+/// `client.kubeConfigGlob` setting are appended after those.
+pub fn kubeconfig_paths() -> Result<Vec<PathBuf>, SerializableKubeError> {
+    return resolve_kubeconfig_paths().map(|(paths, _)| paths);
+}
+
+fn glob_base_dir(pattern: &str) -> PathBuf {
+    return Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '['])
+        })
+        .collect();
+}
+
+/// Returns the kubeconfig files in merge order, together with the directories
+/// that have to be watched to notice changes to them.
+fn resolve_kubeconfig_paths() -> Result<(Vec<PathBuf>, Vec<PathBuf>), SerializableKubeError> {
+    let settings = read_settings()?;
+    let configured = settings["client"]["currentKubeConfig"]
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .or_else(|| env::var("KUBECONFIG").ok().filter(|value| !value.is_empty()));
+
+    let mut directories: Vec<PathBuf> = Vec::new();
+    let mut paths: Vec<PathBuf> = match configured {
+        Some(value) => env::split_paths(&value)
+            .filter(|path| !path.as_os_str().is_empty())
+            .map(|path| PathBuf::from(expand_home(&path.to_string_lossy())))
+            .collect(),
+        None => path::home_dir()
+            .map(|home| vec![home.join(".kube").join("config")])
+            .unwrap_or_default(),
+    };
+
+    if let Some(pattern) = settings["client"]["kubeConfigGlob"]
+        .as_str()
+        .filter(|value| !value.is_empty())
+    {
+        let pattern = expand_home(pattern);
+        directories.push(glob_base_dir(&pattern));
+
+        if let Ok(matches) = glob::glob(&pattern) {
+            let mut matches: Vec<PathBuf> = matches.filter_map(Result::ok).collect();
+            matches.sort();
+            paths.extend(matches);
+        }
+    }
+
+    let mut seen = Vec::new();
+    paths.retain(|path| {
+        if seen.contains(path) {
+            return false;
+        }
+        seen.push(path.clone());
+        return true;
+    });
+
+    directories.extend(paths.iter().filter_map(|path| path.parent().map(Path::to_path_buf)));
+    directories.sort();
+    directories.dedup();
+
+    return Ok((paths, directories));
+}
+
+/// Loads and merges all kubeconfig files the way kubectl does: the first file
+/// to define a context, cluster or user wins, and files that don't exist are skipped.
+pub fn load_kubeconfig() -> Result<MergedKubeconfig, SerializableKubeError> {
+    let paths = kubeconfig_paths()?;
+    let mut merged: Option<Kubeconfig> = None;
+    let mut context_sources = HashMap::new();
+
+    for path in &paths {
+        if !path.is_file() {
+            continue;
+        }
+
+        let kubeconfig = Kubeconfig::read_from(path).map_err(|err| kubeconfig_error(path, err))?;
+        for context in &kubeconfig.contexts {
+            context_sources
+                .entry(context.name.clone())
+                .or_insert_with(|| path.clone());
+        }
+
+        merged = Some(match merged {
+            Some(merged) => merged.merge(kubeconfig).map_err(|err| kubeconfig_error(path, err))?,
+            None => kubeconfig,
+        });
+    }
+
+    let kubeconfig = merged.ok_or_else(|| {
+        let files: Vec<String> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+
+        SerializableKubeError::new(
+            ErrorKind::KubeconfigNotFound,
+            format!("No kubeconfig found, looked in {}", files.join(", ")),
+        )
+        .with_details(serde_json::json!({ "files": files }))
+    })?;
+
+    return Ok(MergedKubeconfig {
+        kubeconfig,
+        context_sources,
+    });
+}
+
+/// Returns the user entry the given context authenticates with.
+pub fn find_auth_info(
+    kubeconfig: &Kubeconfig,
+    context: &str,
+) -> Result<NamedAuthInfo, SerializableKubeError> {
+    let context_auth_info = kubeconfig
+        .contexts
+        .iter()
+        .find(|c| c.name == context)
+        .and_then(|c| c.context.as_ref().map(|context| context.user.clone()))
+        .ok_or(SerializableKubeError::new(
+            ErrorKind::ContextNotFound,
+            "Context not found".to_string(),
+        ))?;
+
+    let auth_info = kubeconfig
+        .auth_infos
+        .iter()
+        .find(|a| a.name == context_auth_info)
+        .ok_or(SerializableKubeError::new(
+            ErrorKind::ContextNotFound,
+            "Auth info not found".to_string(),
+        ))?;
+
+    return Ok(auth_info.clone());
+}
+
+/// The values of `flag` in the args of an exec plugin, given either as
+/// `--flag value` or `--flag=value`.
+pub(crate) fn arg_values(args: &[String], flag: &str) -> Vec<String> {
+    let prefix = format!("{}=", flag);
+
+    return args
+        .iter()
+        .enumerate()
+        .filter_map(|(index, arg)| {
+            if arg == flag {
+                return args.get(index + 1).cloned();
+            }
+            return arg.strip_prefix(&prefix).map(|value| value.to_string());
+        })
+        .collect();
+}
+
+pub(crate) fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    return arg_values(args, flag).into_iter().next();
+}
+
+/// The environment a kubeconfig sets for its exec plugin.
+pub(crate) fn exec_env(exec: &ExecConfig) -> HashMap<String, String> {
+    return exec
+        .env
+        .iter()
+        .flatten()
+        .filter_map(|env| Some((env.get("name")?.clone(), env.get("value")?.clone())))
+        .collect();
+}
+
+#[tauri::command]
+pub async fn list_context_sources() -> Result<Vec<ContextSource>, SerializableKubeError> {
+    let merged = load_kubeconfig()?;
+
+    return Ok(merged
+        .kubeconfig
+        .contexts
+        .iter()
+        .map(|context| ContextSource {
+            name: context.name.clone(),
+            cluster: context.context.as_ref().map(|c| c.cluster.clone()),
+            user: context.context.as_ref().map(|c| c.user.clone()),
+            namespace: context.context.as_ref().and_then(|c| c.namespace.clone()),
+            source: merged
+                .context_sources
+                .get(&context.name)
+                .map(|path| path.to_string_lossy().to_string()),
+        })
+        .collect());
+}
+
+/// Fingerprints every context by its own, its cluster's and its user's entry,
+/// so a rewritten token or server url counts as a modification of the context.
+fn context_fingerprints(kubeconfig: &Kubeconfig) -> HashMap<String, String> {
+    return kubeconfig
+        .contexts
+        .iter()
+        .map(|named_context| {
+            let context = named_context.context.as_ref();
+            let cluster = context.and_then(|c| {
+                kubeconfig.clusters.iter().find(|cluster| cluster.name == c.cluster)
+            });
+            let user = context.and_then(|c| {
+                kubeconfig.auth_infos.iter().find(|auth_info| auth_info.name == c.user)
+            });
+            let fingerprint = serde_json::to_string(&(named_context, cluster, user)).unwrap_or_default();
+
+            (named_context.name.clone(), fingerprint)
+        })
+        .collect();
+}
+
+fn diff_contexts(
+    previous: &HashMap<String, String>,
+    current: &HashMap<String, String>,
+) -> KubeconfigChangedPayload {
+    let mut added: Vec<String> = current
+        .keys()
+        .filter(|name| !previous.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut removed: Vec<String> = previous
+        .keys()
+        .filter(|name| !current.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut modified: Vec<String> = current
+        .iter()
+        .filter(|(name, fingerprint)| previous.get(*name).is_some_and(|old| old != *fingerprint))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    added.sort();
+    removed.sort();
+    modified.sort();
+
+    return KubeconfigChangedPayload {
+        added,
+        removed,
+        modified,
+    };
+}
+
+fn current_fingerprints() -> Result<HashMap<String, String>, SerializableKubeError> {
+    return load_kubeconfig().map(|merged| context_fingerprints(&merged.kubeconfig));
+}
+
+/// Watches the directories holding the active kubeconfig files (and the
+/// settings file, which decides which files are active) and emits a
+/// `kubeconfig_changed` event whenever contexts are added, removed or modified.
+/// Failures are emitted as `kubeconfig_error`; a kubeconfig that can't be
+/// loaded is not treated as having no contexts, the previous ones are kept
+/// until it loads again.
+///
+/// Directories are watched instead of the files themselves because tools like
+/// `aws eks update-kubeconfig` replace the file rather than writing into it.
+pub fn watch_kubeconfig(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                let error = SerializableKubeError::new(
+                    ErrorKind::Unknown,
+                    format!("Unable to watch kubeconfig: {}", err),
+                );
+                let _ = app_handle.emit_all("kubeconfig_error", error);
+                return;
+            }
+        };
+
+        let mut watched: HashSet<PathBuf> = HashSet::new();
+        let mut fingerprints = current_fingerprints().unwrap_or_default();
+
+        loop {
+            let mut directories = resolve_kubeconfig_paths()
+                .map(|(_, directories)| directories)
+                .unwrap_or_default();
+            if let Some(settings_dir) = SETTINGS_FILE.get().and_then(|file| file.parent()) {
+                directories.push(settings_dir.to_path_buf());
+            }
+
+            let wanted: HashSet<PathBuf> = directories.into_iter().filter(|dir| dir.is_dir()).collect();
+            for dir in watched.difference(&wanted) {
+                let _ = watcher.unwatch(dir);
+            }
+            for dir in wanted.difference(&watched) {
+                if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    let error = SerializableKubeError::new(
+                        ErrorKind::Unknown,
+                        format!("Unable to watch {}: {}", dir.display(), err),
+                    );
+                    let _ = app_handle.emit_all("kubeconfig_error", error);
+                }
+            }
+            watched = wanted;
+
+            // Wait for a change, then let the burst of events a rewrite causes settle.
+            if rx.recv().is_err() {
+                return;
+            }
+            while rx.recv_timeout(Duration::from_millis(300)).is_ok() {}
+
+            let current = match current_fingerprints() {
+                Ok(current) => current,
+                Err(error) => {
+                    let _ = app_handle.emit_all("kubeconfig_error", error);
+                    continue;
+                }
+            };
+            let changes = diff_contexts(&fingerprints, &current);
+            fingerprints = current;
+
+            if changes.added.is_empty() && changes.removed.is_empty() && changes.modified.is_empty() {
+                continue;
+            }
+
+            for context in changes.removed.iter().chain(changes.modified.iter()) {
+                invalidate_client(context);
+                invalidate_api_resources(context);
+            }
+
+            let _ = app_handle.emit_all("kubeconfig_changed", changes);
+        }
+    });
+}
@@ -0,0 +1,497 @@
+use crate::log_pipeline::LogPipeline;
+use crate::logs::{aggregate_selector, parse_line, LogLine, LogOptions};
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::AsyncBufReadExt;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ListParams, LogParams};
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// How much is buffered before it is handed to a blocking thread to write.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogExportFormat {
+    Plain,
+    Jsonl,
+}
+
+/// What to export: a single pod, or every pod selected by a label selector
+/// or a workload.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogExportTarget {
+    pub pod: Option<String>,
+    pub label_selector: Option<String>,
+    pub owner_kind: Option<String>,
+    pub owner_name: Option<String>,
+}
+
+/// Payload of the `log_export_progress_<subscription_id>` events. The last
+/// one is sent with `done` once the file is complete.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExportProgress {
+    pub pod: String,
+    pub container: String,
+    pub lines: u64,
+    pub bytes: u64,
+    pub done: bool,
+}
+
+/// A container whose logs could not be read, which doesn't stop the export
+/// of the others.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExportFailure {
+    pub pod: String,
+    pub container: String,
+    pub message: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExportResult {
+    pub path: String,
+    pub lines: u64,
+    pub bytes: u64,
+    pub failures: Vec<LogExportFailure>,
+}
+
+enum ExportOutput {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl ExportOutput {
+    fn write_all(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        return match self {
+            ExportOutput::Plain(file) => file.write_all(chunk),
+            ExportOutput::Gzip(encoder) => encoder.write_all(chunk),
+        };
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        return match self {
+            ExportOutput::Plain(file) => file.flush(),
+            ExportOutput::Gzip(encoder) => encoder.try_finish().and_then(|_| encoder.get_mut().flush()),
+        };
+    }
+}
+
+/// A file writer that is gzip compressed when asked for, and keeps count of
+/// what was written before compression.
+///
+/// Lines are buffered and written in chunks on a blocking thread, into a
+/// `.part` file next to `path` that only replaces `path` once the export is
+/// complete, so a failed export never leaves a truncated file behind.
+struct ExportWriter {
+    output: Arc<Mutex<ExportOutput>>,
+    path: String,
+    part_path: String,
+    pending: Vec<u8>,
+    lines: u64,
+    bytes: u64,
+}
+
+fn write_error(err: std::io::Error) -> SerializableKubeError {
+    return SerializableKubeError::new(ErrorKind::Unknown, format!("Could not write logs: {}", err));
+}
+
+async fn blocking<T: Send + 'static>(
+    task: impl FnOnce() -> std::io::Result<T> + Send + 'static,
+) -> Result<T, SerializableKubeError> {
+    return tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, format!("{:?}", err)))?
+        .map_err(write_error);
+}
+
+impl ExportWriter {
+    async fn create(path: &str, gzip: bool) -> Result<ExportWriter, SerializableKubeError> {
+        let part_path = format!("{}.part", path);
+
+        let file = blocking({
+            let part_path = part_path.clone();
+            move || File::create(part_path)
+        })
+        .await?;
+        let file = BufWriter::new(file);
+        let output = if gzip {
+            ExportOutput::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            ExportOutput::Plain(file)
+        };
+
+        return Ok(ExportWriter {
+            output: Arc::new(Mutex::new(output)),
+            path: path.to_string(),
+            part_path,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+            lines: 0,
+            bytes: 0,
+        });
+    }
+
+    async fn write_line(&mut self, line: &[u8]) -> Result<(), SerializableKubeError> {
+        self.pending.extend_from_slice(line);
+        self.lines += 1;
+        self.bytes += line.len() as u64;
+
+        if self.pending.len() >= CHUNK_SIZE {
+            self.flush().await?;
+        }
+        return Ok(());
+    }
+
+    async fn flush(&mut self) -> Result<(), SerializableKubeError> {
+        let chunk = std::mem::replace(&mut self.pending, Vec::with_capacity(CHUNK_SIZE));
+        let output = self.output.clone();
+
+        return blocking(move || output.lock().unwrap().write_all(&chunk)).await;
+    }
+
+    /// Writes what is left and moves the file into place, returning its size.
+    async fn commit(mut self) -> Result<u64, SerializableKubeError> {
+        if let Err(err) = self.flush().await {
+            self.discard().await;
+            return Err(err);
+        }
+
+        let output = self.output.clone();
+        let (path, part_path) = (self.path.clone(), self.part_path.clone());
+        let committed = blocking(move || {
+            output.lock().unwrap().finish()?;
+            std::fs::rename(&part_path, &path)?;
+            return std::fs::metadata(&path).map(|metadata| metadata.len());
+        })
+        .await;
+
+        if committed.is_err() {
+            self.discard().await;
+        }
+        return committed;
+    }
+
+    /// Closes and removes the partial file.
+    async fn discard(self) {
+        let part_path = self.part_path;
+        let output = self.output;
+
+        let _ = blocking(move || {
+            drop(output);
+            return std::fs::remove_file(part_path);
+        })
+        .await;
+    }
+}
+
+fn format_line(line: &LogLine, format: LogExportFormat, tagged: bool) -> Vec<u8> {
+    let mut formatted = match format {
+        LogExportFormat::Jsonl => serde_json::to_string(line).unwrap_or_default(),
+        LogExportFormat::Plain => {
+            let mut text = String::new();
+            if tagged {
+                text.push_str(&format!(
+                    "[{}/{}] ",
+                    line.pod,
+                    line.container.clone().unwrap_or_default()
+                ));
+            }
+            if let Some(timestamp) = line.timestamp {
+                text.push_str(&timestamp.to_rfc3339());
+                text.push(' ');
+            }
+            text.push_str(&line.message);
+            text
+        }
+    };
+    formatted.push('\n');
+
+    return formatted.into_bytes();
+}
+
+/// Whether `line` was logged after `until_time`, which ends the export of its
+/// container. Lines without a timestamp never do.
+fn past_until(line: &LogLine, until_time: Option<DateTime<Utc>>) -> bool {
+    return match (until_time, line.timestamp) {
+        (Some(until), Some(timestamp)) => timestamp > until,
+        _ => false,
+    };
+}
+
+fn progress(pod: &str, container: &str, writer: &ExportWriter, done: bool) -> LogExportProgress {
+    return LogExportProgress {
+        pod: pod.to_string(),
+        container: container.to_string(),
+        lines: writer.lines,
+        bytes: writer.bytes,
+        done,
+    };
+}
+
+/// Reads the logs of one container from `since_time` up to `until_time` and
+/// streams them through `pipeline` into `writer`.
+///
+/// Failing to read the logs is returned as `Ok(Some(error))`, so the export
+/// goes on with the next container; failing to write them is an `Err` that
+/// ends it.
+async fn export_container(
+    app_handle: &tauri::AppHandle,
+    event_name: &str,
+    pod_api: &Api<Pod>,
+    pod: &str,
+    container: &str,
+    options: &LogOptions,
+    since_time: Option<DateTime<Utc>>,
+    until_time: Option<DateTime<Utc>>,
+    pipeline: &mut LogPipeline,
+    format: LogExportFormat,
+    tagged: bool,
+    writer: &mut ExportWriter,
+) -> Result<Option<SerializableKubeError>, SerializableKubeError> {
+    let log_params = LogParams {
+        container: Some(container.to_string()),
+        previous: options.previous,
+        since_time,
+        since_seconds: if since_time.is_none() { options.since_seconds } else { None },
+        tail_lines: options.tail_lines,
+        timestamps: true,
+        ..LogParams::default()
+    };
+
+    let stream = match pod_api.log_stream(pod, &log_params).await {
+        Ok(stream) => stream,
+        Err(err) => return Ok(Some(SerializableKubeError::from(err))),
+    };
+    let mut stream = Box::pin(stream);
+
+    let container_name = container.to_string();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut progress_at = Instant::now();
+
+    loop {
+        buffer.clear();
+        let read = match stream.read_until(b'\n', &mut buffer).await {
+            Ok(read) => read,
+            Err(err) => return Ok(Some(SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))),
+        };
+        if read == 0 {
+            break;
+        }
+
+        let raw = String::from_utf8_lossy(&buffer);
+        let line = parse_line(pod, Some(&container_name), &raw);
+        if past_until(&line, until_time) {
+            break;
+        }
+
+        if let Some(line) = pipeline.process(line) {
+            writer.write_line(&format_line(&line, format, tagged)).await?;
+        }
+
+        if progress_at.elapsed() >= PROGRESS_INTERVAL {
+            progress_at = Instant::now();
+            let _ = app_handle.emit_all(event_name, progress(pod, container, writer, false));
+        }
+    }
+
+    let _ = app_handle.emit_all(event_name, progress(pod, container, writer, false));
+    return Ok(None);
+}
+
+/// Only containers that restarted have previous logs; asking for the others
+/// fails, which shouldn't abort an export across many pods.
+fn has_restarted(pod: &Pod, container: &str) -> bool {
+    return pod
+        .status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .iter()
+        .flat_map(|statuses| statuses.iter())
+        .any(|status| status.name == container && status.restart_count > 0);
+}
+
+/// Writes the logs of a pod, or of every pod matching a selector or owned by
+/// a workload, to `path` as plain text or JSON lines, optionally gzipped.
+/// Lines are streamed to disk one container after another and pass through
+/// the same filters as live logs. Containers whose logs can't be read are
+/// listed in `failures`. Progress is reported on
+/// `log_export_progress_<subscription_id>`.
+#[tauri::command]
+pub async fn export_logs(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    target: LogExportTarget,
+    path: &str,
+    format: LogExportFormat,
+    gzip: bool,
+    since_time: Option<DateTime<Utc>>,
+    until_time: Option<DateTime<Utc>>,
+    options: LogOptions,
+) -> Result<LogExportResult, SerializableKubeError> {
+    let mut pipeline = LogPipeline::new(&options.filter, false)?;
+    let client = client_with_context(context).await?;
+    let pod_api: Api<Pod> = Api::namespaced(client, namespace);
+
+    let pods: Vec<Pod> = match &target.pod {
+        Some(pod) => vec![pod_api
+            .get(pod)
+            .await
+            .map_err(|err| SerializableKubeError::from(err))?],
+        None => {
+            let selector = aggregate_selector(
+                context,
+                namespace,
+                target.label_selector.as_deref().unwrap_or(""),
+                target.owner_kind.as_deref().unwrap_or(""),
+                target.owner_name.as_deref().unwrap_or(""),
+            )
+            .await?;
+
+            let mut list_params = ListParams::default();
+            if !selector.is_empty() {
+                list_params = list_params.labels(&selector);
+            }
+
+            pod_api
+                .list(&list_params)
+                .await
+                .map_err(|err| SerializableKubeError::from(err))?
+                .items
+        }
+    };
+    let tagged = target.pod.is_none();
+
+    let event_name = format!("log_export_progress_{}", subscription_id);
+    let mut writer = ExportWriter::create(path, gzip).await?;
+    let mut failures: Vec<LogExportFailure> = Vec::new();
+    let mut last = (String::new(), String::new());
+
+    for pod in &pods {
+        let containers: Vec<String> = pod
+            .spec
+            .iter()
+            .flat_map(|spec| spec.containers.iter())
+            .map(|container| container.name.clone())
+            .filter(|name| options.container.as_ref().is_none_or(|container| container == name))
+            .filter(|name| !tagged || !options.previous || has_restarted(pod, name))
+            .collect();
+
+        for container in containers {
+            let exported = export_container(
+                &app_handle,
+                &event_name,
+                &pod_api,
+                &pod.name_any(),
+                &container,
+                &options,
+                since_time,
+                until_time,
+                &mut pipeline,
+                format,
+                tagged,
+                &mut writer,
+            )
+            .await;
+
+            match exported {
+                Ok(None) => {}
+                Ok(Some(err)) => failures.push(LogExportFailure {
+                    pod: pod.name_any(),
+                    container: container.clone(),
+                    message: err.message,
+                }),
+                Err(err) => {
+                    writer.discard().await;
+                    return Err(err);
+                }
+            }
+            last = (pod.name_any(), container);
+        }
+    }
+
+    let lines = writer.lines;
+    let done = progress(&last.0, &last.1, &writer, true);
+    let bytes = writer.commit().await?;
+    let _ = app_handle.emit_all(&event_name, done);
+
+    return Ok(LogExportResult {
+        path: path.to_string(),
+        lines,
+        bytes,
+        failures,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn line(message: &str, timestamp: Option<DateTime<Utc>>) -> LogLine {
+        let mut line = parse_line("web-0", Some(&"app".to_string()), message);
+        line.timestamp = timestamp;
+        return line;
+    }
+
+    #[test]
+    fn plain_lines_are_tagged_for_aggregated_exports() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let line = line("started", Some(timestamp));
+
+        assert_eq!(
+            String::from_utf8(format_line(&line, LogExportFormat::Plain, true)).unwrap(),
+            "[web-0/app] 2024-01-01T12:00:00+00:00 started\n"
+        );
+        assert_eq!(
+            String::from_utf8(format_line(&line, LogExportFormat::Plain, false)).unwrap(),
+            "2024-01-01T12:00:00+00:00 started\n"
+        );
+    }
+
+    #[test]
+    fn api_server_timestamps_are_written_once() {
+        let line = parse_line("web-0", Some(&"app".to_string()), "2024-01-01T12:00:00Z level=info msg=started\n");
+
+        assert_eq!(line.message, "level=info msg=started");
+        assert_eq!(
+            String::from_utf8(format_line(&line, LogExportFormat::Plain, false)).unwrap(),
+            "2024-01-01T12:00:00+00:00 level=info msg=started\n"
+        );
+    }
+
+    #[test]
+    fn jsonl_lines_are_one_object_per_line() {
+        let formatted = String::from_utf8(format_line(&line("started", None), LogExportFormat::Jsonl, true)).unwrap();
+
+        assert!(formatted.ends_with('\n'));
+        assert_eq!(formatted.matches('\n').count(), 1);
+        let value: serde_json::Value = serde_json::from_str(formatted.trim_end()).unwrap();
+        assert_eq!(value["message"], "started");
+        assert_eq!(value["pod"], "web-0");
+    }
+
+    #[test]
+    fn until_time_cuts_off_later_lines_only() {
+        let until = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        assert!(!past_until(&line("before", Some(until - chrono::Duration::seconds(1))), Some(until)));
+        assert!(!past_until(&line("at", Some(until)), Some(until)));
+        assert!(past_until(&line("after", Some(until + chrono::Duration::seconds(1))), Some(until)));
+        assert!(!past_until(&line("untimed", None), Some(until)));
+        assert!(!past_until(&line("unbounded", Some(until)), None));
+    }
+}
@@ -0,0 +1,400 @@
+use crate::logs::LogLine;
+use crate::{ErrorKind, SerializableKubeError};
+use chrono::SecondsFormat;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+const LEVEL_FIELDS: [&str; 5] = ["level", "lvl", "severity", "log.level", "loglevel"];
+/// Words taken as a level when a plain text line starts with them, kept
+/// narrower than `LogLevel::parse` so a line like "Alert sent" isn't fatal.
+const BARE_LEVELS: [&str; 10] = [
+    "trace", "debug", "info", "warn", "warning", "error", "err", "fatal", "panic", "critical",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn parse(value: &str) -> Option<LogLevel> {
+        return match value.to_ascii_lowercase().as_str() {
+            "trace" | "trc" => Some(LogLevel::Trace),
+            "debug" | "dbg" => Some(LogLevel::Debug),
+            "info" | "inf" | "information" | "notice" => Some(LogLevel::Info),
+            "warn" | "wrn" | "warning" => Some(LogLevel::Warn),
+            "error" | "err" | "eror" => Some(LogLevel::Error),
+            "fatal" | "ftl" | "panic" | "critical" | "crit" | "emergency" | "alert" => Some(LogLevel::Fatal),
+            _ => None,
+        };
+    }
+
+    fn name(&self) -> &'static str {
+        return match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+            LogLevel::Fatal => "fatal",
+        };
+    }
+}
+
+/// What the UI asks the log pipeline to keep. `fields` must all match the
+/// parsed fields of a line, where `level` is compared with the detected level.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogFilter {
+    pub include: Option<String>,
+    pub exclude: Option<String>,
+    pub min_level: Option<LogLevel>,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// A compiled `LogFilter`, applied to every line before it is emitted so the
+/// webview only receives what it shows.
+///
+/// Lines without a level of their own, like the rest of a stack trace, take
+/// the level of the line before them from the same container.
+///
+/// Filters always see the message without its timestamp; with `timestamps`
+/// the prefix is put back on the lines that pass, for display.
+pub struct LogPipeline {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    min_level: Option<LogLevel>,
+    fields: BTreeMap<String, String>,
+    timestamps: bool,
+    last_levels: HashMap<(String, Option<String>), LogLevel>,
+}
+
+fn compile(pattern: Option<&String>) -> Result<Option<Regex>, SerializableKubeError> {
+    let pattern = match pattern.filter(|pattern| !pattern.is_empty()) {
+        Some(pattern) => pattern,
+        None => return Ok(None),
+    };
+
+    return RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map(Some)
+        .map_err(|err| {
+            SerializableKubeError::new(ErrorKind::Invalid, format!("Invalid filter: {}", err))
+        });
+}
+
+/// Flattens a json object into dotted keys, `{"http":{"status":500}}` becomes
+/// `http.status=500`.
+fn flatten_json(prefix: &str, value: &serde_json::Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten_json(&path, value, fields);
+            }
+        }
+        serde_json::Value::String(text) => {
+            fields.insert(prefix.to_string(), text.clone());
+        }
+        serde_json::Value::Null => {}
+        other => {
+            fields.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// Splits a line on spaces outside of double quotes, unescaping the quoted
+/// parts.
+fn logfmt_tokens(message: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for char in message.chars() {
+        match char {
+            _ if escaped => {
+                token.push(char);
+                escaped = false;
+            }
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ' ' if !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            _ => token.push(char),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    return tokens;
+}
+
+/// Parses `key=value key2="quoted value"` lines. Lines with fewer than two
+/// pairs or with bare words are treated as plain text.
+fn parse_logfmt(message: &str) -> Option<BTreeMap<String, String>> {
+    let tokens = logfmt_tokens(message);
+    if tokens.len() < 2 {
+        return None;
+    }
+
+    let mut fields = BTreeMap::new();
+    for token in tokens {
+        let (key, value) = token.split_once('=')?;
+        if key.is_empty() {
+            return None;
+        }
+        fields.insert(key.to_string(), value.to_string());
+    }
+
+    return Some(fields);
+}
+
+fn parse_fields(message: &str) -> BTreeMap<String, String> {
+    let trimmed = message.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(value @ serde_json::Value::Object(_)) = serde_json::from_str(trimmed) {
+            let mut fields = BTreeMap::new();
+            flatten_json("", &value, &mut fields);
+            return fields;
+        }
+    }
+
+    return parse_logfmt(trimmed).unwrap_or_default();
+}
+
+impl LogPipeline {
+    pub fn new(filter: &LogFilter, timestamps: bool) -> Result<LogPipeline, SerializableKubeError> {
+        return Ok(LogPipeline {
+            include: compile(filter.include.as_ref())?,
+            exclude: compile(filter.exclude.as_ref())?,
+            min_level: filter.min_level,
+            fields: filter.fields.clone(),
+            timestamps,
+            last_levels: HashMap::new(),
+        });
+    }
+
+    fn detect_level(line: &LogLine) -> Option<LogLevel> {
+        if let Some(level) = LEVEL_FIELDS
+            .iter()
+            .filter_map(|field| line.fields.get(*field))
+            .find_map(|value| LogLevel::parse(value))
+        {
+            return Some(level);
+        }
+
+        // klog style prefixes, like `E0101 12:00:00.000000 ...`
+        let bytes = line.message.as_bytes();
+        if bytes.len() > 5 && bytes[1..5].iter().all(u8::is_ascii_digit) && bytes[5] == b' ' {
+            let level = match bytes[0] {
+                b'I' => Some(LogLevel::Info),
+                b'W' => Some(LogLevel::Warn),
+                b'E' => Some(LogLevel::Error),
+                b'F' => Some(LogLevel::Fatal),
+                _ => None,
+            };
+            if level.is_some() {
+                return level;
+            }
+        }
+
+        // A bare level word leading the line, like `ERROR ...` or `[warn] ...`,
+        // after any date and time.
+        let leading = line
+            .message
+            .split_whitespace()
+            .find(|token| !token.starts_with(|char: char| char.is_ascii_digit()))?
+            .trim_matches(|char: char| !char.is_ascii_alphabetic())
+            .to_ascii_lowercase();
+        if !BARE_LEVELS.contains(&leading.as_str()) {
+            return None;
+        }
+        return LogLevel::parse(&leading);
+    }
+
+    /// Parses and classifies `line`, returning `None` when the filter drops it.
+    pub fn process(&mut self, mut line: LogLine) -> Option<LogLine> {
+        line.fields = parse_fields(&line.message);
+
+        let source = (line.pod.clone(), line.container.clone());
+        line.level = match LogPipeline::detect_level(&line) {
+            Some(level) => {
+                self.last_levels.insert(source, level);
+                Some(level)
+            }
+            None => self.last_levels.get(&source).copied(),
+        };
+
+        if let Some(include) = &self.include {
+            if !include.is_match(&line.message) {
+                return None;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(&line.message) {
+                return None;
+            }
+        }
+
+        if let (Some(min_level), Some(level)) = (self.min_level, line.level) {
+            if level < min_level {
+                return None;
+            }
+        }
+
+        let fields_match = self.fields.iter().all(|(key, expected)| {
+            if key == "level" {
+                return line.level.is_some_and(|level| level.name() == expected.to_ascii_lowercase());
+            }
+            line.fields.get(key) == Some(expected)
+        });
+        if !fields_match {
+            return None;
+        }
+
+        if self.timestamps {
+            if let Some(timestamp) = line.timestamp {
+                line.message = format!(
+                    "{} {}",
+                    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    line.message
+                );
+            }
+        }
+
+        return Some(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::parse_line;
+
+    fn line(container: &str, message: &str) -> LogLine {
+        return parse_line("web-0", Some(&container.to_string()), message);
+    }
+
+    fn pipeline(filter: LogFilter) -> LogPipeline {
+        return LogPipeline::new(&filter, false).unwrap();
+    }
+
+    fn level_of(message: &str) -> Option<LogLevel> {
+        return pipeline(LogFilter::default()).process(line("app", message)).unwrap().level;
+    }
+
+    #[test]
+    fn json_is_flattened_into_dotted_fields() {
+        let fields = parse_fields(r#"{"msg":"done","http":{"status":500,"ok":false},"trace":null}"#);
+
+        assert_eq!(fields.get("msg").map(String::as_str), Some("done"));
+        assert_eq!(fields.get("http.status").map(String::as_str), Some("500"));
+        assert_eq!(fields.get("http.ok").map(String::as_str), Some("false"));
+        assert!(!fields.contains_key("trace"));
+    }
+
+    #[test]
+    fn logfmt_pairs_are_unquoted() {
+        let fields = parse_fields(r#"level=warn msg="disk \"data\" almost full" used=91%"#);
+
+        assert_eq!(fields.get("level").map(String::as_str), Some("warn"));
+        assert_eq!(fields.get("msg").map(String::as_str), Some(r#"disk "data" almost full"#));
+        assert_eq!(fields.get("used").map(String::as_str), Some("91%"));
+    }
+
+    #[test]
+    fn plain_text_has_no_fields() {
+        assert!(parse_fields("GET /healthz 200").is_empty());
+        assert!(parse_fields("key=value and some words").is_empty());
+        assert!(parse_fields("only=one").is_empty());
+    }
+
+    #[test]
+    fn level_fields_win_over_the_message() {
+        assert_eq!(level_of(r#"{"severity":"ERROR","msg":"info about the failure"}"#), Some(LogLevel::Error));
+        assert_eq!(level_of("lvl=dbg msg=tick"), Some(LogLevel::Debug));
+    }
+
+    #[test]
+    fn klog_prefixes_are_recognised() {
+        assert_eq!(level_of("E0101 12:00:00.000000       1 controller.go:42] sync failed"), Some(LogLevel::Error));
+        assert_eq!(level_of("W0101 12:00:00.000000       1 reflector.go:7] watch closed"), Some(LogLevel::Warn));
+        assert_eq!(level_of("I0101 12:00:00.000000       1 main.go:1] starting"), Some(LogLevel::Info));
+    }
+
+    #[test]
+    fn bare_levels_only_count_when_leading() {
+        assert_eq!(level_of("ERROR could not connect"), Some(LogLevel::Error));
+        assert_eq!(level_of("[warn] retrying"), Some(LogLevel::Warn));
+        assert_eq!(level_of("2024-01-01 12:00:00 INFO: started"), Some(LogLevel::Info));
+        assert_eq!(level_of("request finished without error"), None);
+        assert_eq!(level_of("GET /debug/pprof 200"), None);
+        assert_eq!(level_of("Alert sent to on-call"), None);
+    }
+
+    #[test]
+    fn min_level_keeps_lines_without_a_level() {
+        let mut pipeline = pipeline(LogFilter {
+            min_level: Some(LogLevel::Warn),
+            ..LogFilter::default()
+        });
+
+        assert!(pipeline.process(line("app", "listening on :8080")).is_some());
+        assert!(pipeline.process(line("app", "INFO started")).is_none());
+    }
+
+    #[test]
+    fn lines_without_a_level_inherit_the_previous_one_per_container() {
+        let mut pipeline = pipeline(LogFilter {
+            min_level: Some(LogLevel::Error),
+            ..LogFilter::default()
+        });
+
+        assert!(pipeline.process(line("app", "INFO handling request")).is_none());
+        assert!(pipeline.process(line("sidecar", "ERROR upstream reset")).is_some());
+        assert!(pipeline.process(line("app", "    at handler.rs:12")).is_none());
+
+        assert!(pipeline.process(line("app", "ERROR panicked")).is_some());
+        let continuation = pipeline.process(line("app", "    at handler.rs:12")).unwrap();
+        assert_eq!(continuation.level, Some(LogLevel::Error));
+    }
+
+    #[test]
+    fn timestamps_are_added_after_filtering() {
+        let mut pipeline = LogPipeline::new(
+            &LogFilter {
+                min_level: Some(LogLevel::Warn),
+                fields: BTreeMap::from([("trace_id".to_string(), "abc".to_string())]),
+                ..LogFilter::default()
+            },
+            true,
+        )
+        .unwrap();
+
+        let json = line("app", r#"2024-01-01T12:00:00.5Z {"level":"error","trace_id":"abc"}"#);
+        let kept = pipeline.process(json).unwrap();
+        assert_eq!(kept.level, Some(LogLevel::Error));
+        assert_eq!(kept.message, r#"2024-01-01T12:00:00.500Z {"level":"error","trace_id":"abc"}"#);
+
+        let logfmt = line("app", "2024-01-01T12:00:01Z level=warn trace_id=abc msg=slow");
+        assert!(pipeline.process(logfmt).is_some());
+        let other = line("app", "2024-01-01T12:00:02Z level=warn trace_id=def msg=slow");
+        assert!(pipeline.process(other).is_none());
+
+        let klog = line("app", "2024-01-01T12:00:03Z I0101 12:00:03.000000       1 main.go:1] trace_id=abc");
+        assert!(pipeline.process(klog).is_none());
+    }
+}
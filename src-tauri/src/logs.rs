@@ -0,0 +1,632 @@
+use crate::dynamic::{dynamic_api, resolve_api_resource};
+use crate::log_pipeline::{LogFilter, LogLevel, LogPipeline};
+use crate::rollout::label_selector_string;
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::{AsyncBufReadExt, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::api::{Api, LogParams};
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_BATCH_SIZE: usize = 500;
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+static LOG_STREAMS: Mutex<Option<HashMap<String, JoinHandle<()>>>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    pub pod: String,
+    pub container: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: String,
+    pub level: Option<LogLevel>,
+    /// Fields parsed from JSON or logfmt lines.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSource {
+    pub pod: String,
+    pub container: String,
+}
+
+/// Payload of the `logs_<subscription_id>` events. `END` is sent once every
+/// followed container stopped producing logs. Aggregated streams also report
+/// the containers they start and stop following.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", content = "object", rename_all = "UPPERCASE")]
+pub enum LogEvent {
+    Lines(Vec<LogLine>),
+    Attached(LogSource),
+    Detached(LogSource),
+    Error(SerializableKubeError),
+    End,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogOptions {
+    pub container: Option<String>,
+    pub previous: bool,
+    pub since_seconds: Option<i64>,
+    pub tail_lines: Option<i64>,
+    /// Prefixes messages with their timestamp, like `kubectl logs --timestamps`.
+    pub timestamps: bool,
+    pub filter: LogFilter,
+}
+
+/// Tracks the last timestamp handed out, so a stream reopened with
+/// `sinceTime`, which the api server truncates to the second, doesn't repeat
+/// lines we've already seen.
+#[derive(Default)]
+struct ResumePoint {
+    last: Option<DateTime<Utc>>,
+    seen_at_last: usize,
+    replayed_at_last: usize,
+}
+
+impl ResumePoint {
+    fn reconnected(&mut self) {
+        self.replayed_at_last = 0;
+    }
+
+    fn accept(&mut self, timestamp: Option<DateTime<Utc>>) -> bool {
+        let timestamp = match timestamp {
+            Some(timestamp) => timestamp,
+            None => return true,
+        };
+
+        match self.last {
+            Some(last) if timestamp < last => return false,
+            Some(last) if timestamp == last => {
+                self.replayed_at_last += 1;
+                if self.replayed_at_last <= self.seen_at_last {
+                    return false;
+                }
+                self.seen_at_last += 1;
+            }
+            _ => {
+                self.last = Some(timestamp);
+                self.seen_at_last = 1;
+                self.replayed_at_last = 1;
+            }
+        }
+
+        return true;
+    }
+}
+
+/// Splits the RFC 3339 timestamp the api server prefixes lines with when
+/// `timestamps` is requested.
+pub fn parse_line(pod: &str, container: Option<&String>, raw: &str) -> LogLine {
+    let raw = raw.trim_end_matches(['\n', '\r']);
+    let (timestamp, message) = match raw.split_once(' ') {
+        Some((prefix, message)) => match DateTime::parse_from_rfc3339(prefix) {
+            Ok(timestamp) => (Some(timestamp.with_timezone(&Utc)), message),
+            Err(_) => (None, raw),
+        },
+        None => match DateTime::parse_from_rfc3339(raw) {
+            Ok(timestamp) => (Some(timestamp.with_timezone(&Utc)), ""),
+            Err(_) => (None, raw),
+        },
+    };
+
+    return LogLine {
+        pod: pod.to_string(),
+        container: container.cloned(),
+        timestamp,
+        message: message.to_string(),
+        level: None,
+        fields: BTreeMap::new(),
+    };
+}
+
+fn pod_finished(pod: &Pod) -> bool {
+    return matches!(
+        pod.status.as_ref().and_then(|status| status.phase.as_deref()),
+        Some("Succeeded") | Some("Failed")
+    );
+}
+
+/// Follows the logs of one container, sending every line to `sender`. When
+/// the stream drops, it is reopened from the last seen timestamp for as long
+/// as the pod is still running, backing off while the api server can't be
+/// reached. Returns once the pod is gone, the logs of a previous container
+/// have been read, or the receiving side hung up.
+pub async fn follow_container(
+    pod_api: Api<Pod>,
+    pod: String,
+    options: LogOptions,
+    sender: UnboundedSender<Result<LogLine, SerializableKubeError>>,
+) {
+    let mut resume = ResumePoint::default();
+    let mut delay = MIN_RECONNECT_DELAY;
+    let mut reported_error = false;
+
+    loop {
+        let mut log_params = LogParams {
+            container: options.container.clone(),
+            follow: !options.previous,
+            previous: options.previous,
+            timestamps: true,
+            ..LogParams::default()
+        };
+        match resume.last {
+            Some(last) => log_params.since_time = Some(last),
+            None => {
+                log_params.since_seconds = options.since_seconds;
+                log_params.tail_lines = options.tail_lines;
+            }
+        }
+        resume.reconnected();
+
+        match pod_api.log_stream(&pod, &log_params).await {
+            Ok(stream) => {
+                delay = MIN_RECONNECT_DELAY;
+                reported_error = false;
+
+                let mut stream = Box::pin(stream);
+                let mut buffer: Vec<u8> = Vec::new();
+                loop {
+                    buffer.clear();
+                    match stream.read_until(b'\n', &mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+
+                    let raw = String::from_utf8_lossy(&buffer);
+                    let line = parse_line(&pod, options.container.as_ref(), &raw);
+                    if !resume.accept(line.timestamp) {
+                        continue;
+                    }
+                    if sender.unbounded_send(Ok(line)).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(kube::Error::Api(error)) if error.code == 404 => {
+                let _ = sender.unbounded_send(Err(SerializableKubeError::from(kube::Error::Api(error))));
+                return;
+            }
+            Err(err) => {
+                // A container that is still starting refuses log requests, so
+                // only the first failure of a streak is worth reporting.
+                if !reported_error || options.previous {
+                    reported_error = true;
+                    if sender.unbounded_send(Err(SerializableKubeError::from(err))).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        if options.previous {
+            return;
+        }
+
+        // Whatever broke the stream, like a network blip or an expired token,
+        // may also fail this check, which is no reason to stop following.
+        match pod_api.get_opt(&pod).await {
+            Ok(Some(current)) if !pod_finished(&current) => {}
+            Ok(_) => return,
+            Err(err) => {
+                if !reported_error {
+                    reported_error = true;
+                    if sender.unbounded_send(Err(SerializableKubeError::from(err))).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Drains `receiver` through `pipeline` into `LogEvent::Lines` batches,
+/// emitted every `BATCH_INTERVAL` or once `MAX_BATCH_SIZE` lines are pending,
+/// ordered by timestamp within a batch. Sends `LogEvent::End` when all
+/// senders are gone.
+pub async fn emit_batches(
+    app_handle: tauri::AppHandle,
+    event_name: String,
+    mut pipeline: LogPipeline,
+    mut receiver: UnboundedReceiver<Result<LogLine, SerializableKubeError>>,
+) {
+    let mut batch: Vec<LogLine> = Vec::new();
+    let mut flush_at = Instant::now() + BATCH_INTERVAL;
+
+    loop {
+        let wait = flush_at.saturating_duration_since(Instant::now());
+        let (received, closed) = match tokio::time::timeout(wait, receiver.next()).await {
+            Ok(Some(received)) => (Some(received), false),
+            Ok(None) => (None, true),
+            Err(_) => (None, false),
+        };
+
+        match received {
+            Some(Ok(line)) => {
+                if let Some(line) = pipeline.process(line) {
+                    if batch.is_empty() {
+                        flush_at = Instant::now() + BATCH_INTERVAL;
+                    }
+                    batch.push(line);
+                }
+            }
+            Some(Err(error)) => {
+                let event = LogEvent::Error(error);
+                if app_handle.emit_all(&event_name, event).is_err() {
+                    return;
+                }
+            }
+            None => {}
+        }
+
+        let due = closed || batch.len() >= MAX_BATCH_SIZE || Instant::now() >= flush_at;
+        if due && !batch.is_empty() {
+            batch.sort_by_key(|line| line.timestamp);
+            let lines = std::mem::take(&mut batch);
+            if app_handle.emit_all(&event_name, LogEvent::Lines(lines)).is_err() {
+                return;
+            }
+        }
+        if batch.is_empty() {
+            flush_at = Instant::now() + BATCH_INTERVAL;
+        }
+
+        if closed {
+            let _ = app_handle.emit_all(&event_name, LogEvent::End);
+            return;
+        }
+    }
+}
+
+pub fn register_log_stream(subscription_id: &str, handle: JoinHandle<()>) {
+    let mut streams = LOG_STREAMS.lock().unwrap();
+    if let Some(previous) = streams
+        .get_or_insert_with(HashMap::new)
+        .insert(subscription_id.to_string(), handle)
+    {
+        previous.abort();
+    }
+}
+
+/// Follows the logs of a pod container and emits `logs_<subscription_id>`
+/// events with batches of lines until `stop_logs` is called.
+#[tauri::command]
+pub async fn stream_logs(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    name: &str,
+    options: LogOptions,
+) -> Result<(), SerializableKubeError> {
+    let pipeline = LogPipeline::new(&options.filter, options.timestamps)?;
+    let client = client_with_context(context).await?;
+    let pod_api: Api<Pod> = Api::namespaced(client, namespace);
+
+    let event_name = format!("logs_{}", subscription_id);
+    let (sender, receiver) = unbounded();
+    let follow = follow_container(pod_api, name.to_string(), options, sender);
+    let emit = emit_batches(app_handle, event_name, pipeline, receiver);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        futures::future::join(follow, emit).await;
+    });
+
+    register_log_stream(subscription_id, handle);
+    return Ok(());
+}
+
+/// Resolves the pod selector of a workload, or of anything else with a
+/// `spec.selector`. Services use a plain label map instead of a LabelSelector.
+pub async fn owner_selector(
+    context: &str,
+    namespace: &str,
+    kind: &str,
+    name: &str,
+) -> Result<LabelSelector, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let (resource, capabilities) = resolve_api_resource(&client, context, "", "", kind).await?;
+    let owner = dynamic_api(client, &resource, &capabilities, namespace)
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let selector = owner.data.pointer("/spec/selector").cloned().unwrap_or_default();
+    let label_selector = if selector.get("matchLabels").is_some() || selector.get("matchExpressions").is_some() {
+        serde_json::from_value::<LabelSelector>(selector).ok()
+    } else {
+        serde_json::from_value::<BTreeMap<String, String>>(selector)
+            .ok()
+            .map(|labels| LabelSelector {
+                match_labels: Some(labels),
+                ..LabelSelector::default()
+            })
+    };
+
+    return label_selector
+        .filter(|selector| selector.match_labels.is_some() || selector.match_expressions.is_some())
+        .ok_or_else(|| {
+            SerializableKubeError::new(
+                ErrorKind::Invalid,
+                format!("{} {} has no pod selector", resource.kind, name),
+            )
+        });
+}
+
+/// The label selector of the pods to aggregate: the pod selector of the
+/// workload `owner_kind`/`owner_name` when given, otherwise `label_selector`,
+/// which is passed to the api server as is.
+pub async fn aggregate_selector(
+    context: &str,
+    namespace: &str,
+    label_selector: &str,
+    owner_kind: &str,
+    owner_name: &str,
+) -> Result<String, SerializableKubeError> {
+    if owner_kind.is_empty() {
+        return Ok(label_selector.to_string());
+    }
+
+    let selector = owner_selector(context, namespace, owner_kind, owner_name).await?;
+    return Ok(label_selector_string(&selector));
+}
+
+/// Containers of `pod` that have started and therefore have logs to follow.
+fn loggable_containers(pod: &Pod, container: Option<&String>) -> Vec<String> {
+    if pod_finished(pod) {
+        return Vec::new();
+    }
+
+    return pod
+        .status
+        .as_ref()
+        .and_then(|status| status.container_statuses.as_ref())
+        .iter()
+        .flat_map(|statuses| statuses.iter())
+        .filter(|status| container.is_none_or(|container| &status.name == container))
+        .filter(|status| {
+            status.state.as_ref().is_some_and(|state| state.running.is_some() || state.terminated.is_some())
+        })
+        .map(|status| status.name.clone())
+        .collect();
+}
+
+/// The container followers of an aggregated stream. Dropping a `JoinHandle`
+/// leaves its task running, so they are aborted when the stream stops.
+#[derive(Default)]
+struct Followers(HashMap<(String, String), JoinHandle<()>>);
+
+impl Deref for Followers {
+    type Target = HashMap<(String, String), JoinHandle<()>>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.0;
+    }
+}
+
+impl DerefMut for Followers {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.0;
+    }
+}
+
+impl Drop for Followers {
+    fn drop(&mut self) {
+        for handle in self.0.values() {
+            handle.abort();
+        }
+    }
+}
+
+/// Follows the containers of every pod matching `selector`, attaching to pods
+/// as they start and detaching once they are deleted or finished.
+async fn follow_selector(
+    app_handle: tauri::AppHandle,
+    event_name: String,
+    pod_api: Api<Pod>,
+    selector: String,
+    options: LogOptions,
+    sender: UnboundedSender<Result<LogLine, SerializableKubeError>>,
+) {
+    let mut config = watcher::Config::default();
+    if !selector.is_empty() {
+        config = config.labels(&selector);
+    }
+
+    let mut followers = Followers::default();
+    let mut stream = watcher(pod_api.clone(), config).default_backoff().boxed();
+
+    while let Some(event) = stream.next().await {
+        let pods: Vec<Pod> = match event {
+            Ok(watcher::Event::Applied(pod)) => vec![pod],
+            Ok(watcher::Event::Deleted(mut pod)) => {
+                pod.status = None;
+                vec![pod]
+            }
+            Ok(watcher::Event::Restarted(pods)) => {
+                let names: Vec<String> = pods.iter().map(|pod| pod.name_any()).collect();
+                let stale: Vec<(String, String)> = followers
+                    .keys()
+                    .filter(|(pod, _)| !names.contains(pod))
+                    .cloned()
+                    .collect();
+                for key in stale {
+                    if let Some(handle) = followers.remove(&key) {
+                        handle.abort();
+                    }
+                    let source = LogSource { pod: key.0, container: key.1 };
+                    let _ = app_handle.emit_all(&event_name, LogEvent::Detached(source));
+                }
+                pods
+            }
+            Err(err) => {
+                if sender.unbounded_send(Err(SerializableKubeError::from(err))).is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        for pod in &pods {
+            let name = pod.name_any();
+            let containers = loggable_containers(pod, options.container.as_ref());
+
+            let detached: Vec<(String, String)> = followers
+                .keys()
+                .filter(|(follower_pod, container)| follower_pod == &name && !containers.contains(container))
+                .cloned()
+                .collect();
+            for key in detached {
+                if let Some(handle) = followers.remove(&key) {
+                    handle.abort();
+                }
+                let source = LogSource { pod: key.0, container: key.1 };
+                let _ = app_handle.emit_all(&event_name, LogEvent::Detached(source));
+            }
+
+            for container in containers {
+                let key = (name.clone(), container.clone());
+                if followers.contains_key(&key) {
+                    continue;
+                }
+
+                let follow = follow_container(
+                    pod_api.clone(),
+                    name.clone(),
+                    LogOptions {
+                        container: Some(container.clone()),
+                        ..options.clone()
+                    },
+                    sender.clone(),
+                );
+                followers.insert(key, tauri::async_runtime::spawn(follow));
+
+                let source = LogSource { pod: name.clone(), container };
+                let _ = app_handle.emit_all(&event_name, LogEvent::Attached(source));
+            }
+        }
+    }
+}
+
+/// Follows the logs of all pods matching `label_selector`, or selected by the
+/// workload `owner_kind`/`owner_name`, like `stern`. Lines are tagged with
+/// their pod and container and emitted on `logs_<subscription_id>`.
+#[tauri::command]
+pub async fn stream_aggregated_logs(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    label_selector: &str,
+    owner_kind: &str,
+    owner_name: &str,
+    options: LogOptions,
+) -> Result<(), SerializableKubeError> {
+    let pipeline = LogPipeline::new(&options.filter, options.timestamps)?;
+    let selector = aggregate_selector(context, namespace, label_selector, owner_kind, owner_name).await?;
+
+    let client = client_with_context(context).await?;
+    let pod_api: Api<Pod> = Api::namespaced(client, namespace);
+
+    let event_name = format!("logs_{}", subscription_id);
+    let (sender, receiver) = unbounded();
+    let follow = follow_selector(
+        app_handle.clone(),
+        event_name.clone(),
+        pod_api,
+        selector,
+        options,
+        sender,
+    );
+    let emit = emit_batches(app_handle, event_name, pipeline, receiver);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        futures::future::join(follow, emit).await;
+    });
+
+    register_log_stream(subscription_id, handle);
+    return Ok(());
+}
+
+#[tauri::command]
+pub fn stop_logs(subscription_id: &str) {
+    if let Some(streams) = LOG_STREAMS.lock().unwrap().as_mut() {
+        if let Some(handle) = streams.remove(subscription_id) {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64, nanos: u32) -> Option<DateTime<Utc>> {
+        return DateTime::from_timestamp(1_700_000_000 + seconds, nanos);
+    }
+
+    #[test]
+    fn lines_are_accepted_until_a_reconnect() {
+        let mut resume = ResumePoint::default();
+
+        assert!(resume.accept(at(0, 0)));
+        assert!(resume.accept(at(0, 0)));
+        assert!(resume.accept(at(1, 0)));
+        assert!(resume.accept(None));
+        assert_eq!(resume.last, at(1, 0));
+    }
+
+    #[test]
+    fn replayed_lines_are_skipped_after_a_reconnect() {
+        let mut resume = ResumePoint::default();
+        assert!(resume.accept(at(1, 100)));
+        assert!(resume.accept(at(2, 500)));
+        assert!(resume.accept(at(2, 500)));
+
+        // `sinceTime` is truncated to the second, so the replay starts at 2.0.
+        resume.reconnected();
+        assert!(!resume.accept(at(2, 0)));
+        assert!(!resume.accept(at(2, 500)));
+        assert!(!resume.accept(at(2, 500)));
+        assert!(resume.accept(at(2, 500)));
+        assert!(resume.accept(at(3, 0)));
+    }
+
+    #[test]
+    fn lines_without_timestamps_are_never_skipped() {
+        let mut resume = ResumePoint::default();
+        assert!(resume.accept(at(5, 0)));
+
+        resume.reconnected();
+        assert!(resume.accept(None));
+        assert!(!resume.accept(at(5, 0)));
+    }
+
+    #[test]
+    fn parse_line_splits_the_timestamp() {
+        let line = parse_line("web-0", None, "2024-01-01T12:00:00.123456789Z hello world\n");
+
+        assert_eq!(line.message, "hello world");
+        assert_eq!(line.timestamp.unwrap().to_rfc3339(), "2024-01-01T12:00:00.123456789+00:00");
+        assert_eq!(parse_line("web-0", None, "no timestamp").timestamp, None);
+    }
+}
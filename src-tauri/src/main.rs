@@ -1,6 +1,25 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod client_pool;
+mod credentials;
+mod describe;
+mod dynamic;
+mod eks;
+mod error;
+mod events;
+mod kubeconfig;
+mod log_export;
+mod log_pipeline;
+mod logs;
+mod nodes;
+mod oidc;
+mod rollout;
+mod shell_env;
+mod tty;
+mod watch;
+mod workloads;
+
 use either::Either;
 use istio_api_rs::networking::v1beta1::virtual_service::VirtualService;
 use k8s_metrics::v1beta1::PodMetrics;
@@ -14,15 +33,11 @@ use k8s_openapi::api::core::v1::{
     ConfigMap, Namespace, PersistentVolume, PersistentVolumeClaim, Pod, Secret, Service,
 };
 use kube::api::{DeleteParams, ListParams};
-use kube::config::{KubeConfigOptions, Kubeconfig, KubeconfigError, NamedAuthInfo};
-use kube::{api::Api, Client, Config, Error};
-use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use client_pool::client_with_context;
+use error::{ErrorKind, SerializableKubeError};
+use kube::config::NamedAuthInfo;
+use kube::{api::Api, Client};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::ffi::OsString;
-use std::{fs, io::{BufRead, BufReader, Write}, sync::{Arc, Mutex}, thread::{self, sleep}, time::Duration};
-use tauri::api::path;
-use uuid::Uuid;
 
 #[derive(Serialize)]
 enum DeletionResult {
@@ -30,83 +45,22 @@ enum DeletionResult {
     Pending(String),
 }
 
-#[derive(Debug, Serialize)]
-struct SerializableKubeError {
-    message: String,
-    code: Option<u16>,
-    reason: Option<String>,
-    details: Option<String>,
-}
-
 #[derive(Clone, serde::Serialize)]
 struct CheckForUpdatesPayload {}
 
-impl From<Error> for SerializableKubeError {
-    fn from(error: Error) -> Self {
-        println!("Error: {:?}", error);
-
-        match error {
-            Error::Api(api_error) => {
-                let code = api_error.code;
-                let reason = api_error.reason;
-                let message = api_error.message;
-                return SerializableKubeError {
-                    message,
-                    code: Option::from(code),
-                    reason: Option::from(reason),
-                    details: None,
-                };
-            }
-            _ => {
-                return SerializableKubeError {
-                    message: error.to_string(),
-                    code: None,
-                    reason: None,
-                    details: None,
-                };
-            }
-        }
-    }
-}
-
-impl From<KubeconfigError> for SerializableKubeError {
-    fn from(error: KubeconfigError) -> Self {
-        return SerializableKubeError {
-            message: error.to_string(),
-            code: None,
-            reason: None,
-            details: None,
-        };
-    }
-}
-
-static CURRENT_CONTEXT: Mutex<Option<String>> = Mutex::new(Some(String::new()));
-static CLIENT: Mutex<Option<Client>> = Mutex::new(None);
-
-fn get_kube_config(app_handle: tauri::AppHandle) -> Result<Kubeconfig, Error> {
-    let settings_file = app_handle.path_resolver().app_config_dir().unwrap().to_str().unwrap().to_string() + "/settings.json";
-    let user_dir = path::home_dir().unwrap().to_str().unwrap().to_string();
-    let json_string = fs::read_to_string(settings_file).expect("Unable to load file");
-    let json: serde_json::Value = serde_json::from_str(&json_string).expect("Unable to parse file");
-
-    let kubeconfig = json["client"]["currentKubeConfig"].as_str();
-    let fallback_config = (user_dir + "/.kube/config").to_string();
-
-    let file = if kubeconfig.is_none() || kubeconfig.unwrap().is_empty() { &fallback_config } else { kubeconfig.unwrap() };
-    let file = std::path::Path::new(&*file);
-    return Ok(Kubeconfig::read_from(&file).expect("Cannot load kubeconfig"))
-}
-
 #[tauri::command]
-async fn get_current_context(app_handle: tauri::AppHandle) -> Result<String, SerializableKubeError> {
-    let config = get_kube_config(app_handle).map_err(|err| SerializableKubeError::from(err))?;
+async fn get_current_context() -> Result<String, SerializableKubeError> {
+    let config = kubeconfig::load_kubeconfig()?.kubeconfig;
 
-    return Ok(config.current_context.expect("No current context"));
+    return config.current_context.ok_or(SerializableKubeError::new(
+        ErrorKind::NoCurrentContext,
+        "No current-context is set in the kubeconfig".to_string(),
+    ));
 }
 
 #[tauri::command]
-async fn list_contexts(app_handle: tauri::AppHandle) -> Result<Vec<String>, SerializableKubeError> {
-    let config = get_kube_config(app_handle).map_err(|err| SerializableKubeError::from(err))?;
+async fn list_contexts() -> Result<Vec<String>, SerializableKubeError> {
+    let config = kubeconfig::load_kubeconfig()?.kubeconfig;
 
     config
         .contexts
@@ -119,54 +73,10 @@ async fn list_contexts(app_handle: tauri::AppHandle) -> Result<Vec<String>, Seri
 }
 
 #[tauri::command]
-async fn get_context_auth_info(app_handle: tauri::AppHandle, context: &str) -> Result<NamedAuthInfo, SerializableKubeError> {
-    let config = get_kube_config(app_handle).map_err(|err| SerializableKubeError::from(err))?;
-
-    let context_auth_info = config
-        .contexts
-        .iter()
-        .find(|c| c.name == context)
-        .map(|c| c.clone().context.unwrap().user.clone())
-        .ok_or(SerializableKubeError {
-            message: "Context not found".to_string(),
-            code: None,
-            reason: None,
-            details: None,
-        })?;
-
-    let auth_info = config
-        .auth_infos
-        .iter()
-        .find(|a| a.name == context_auth_info)
-        .ok_or(SerializableKubeError {
-            message: "Auth info not found".to_string(),
-            code: None,
-            reason: None,
-            details: None,
-        })?;
-
-    return Ok(auth_info.clone());
-}
-
-async fn client_with_context(context: &str) -> Result<Client, SerializableKubeError> {
-    if context.to_string() != CURRENT_CONTEXT.lock().unwrap().as_ref().unwrap().clone() {
-        let options = KubeConfigOptions {
-            context: Some(context.to_string()),
-            cluster: None,
-            user: None,
-        };
-
-        let client_config = Config::from_kubeconfig(&options)
-            .await
-            .map_err(|err| SerializableKubeError::from(err))?;
-        let client =
-            Client::try_from(client_config).map_err(|err| SerializableKubeError::from(err))?;
-
-        CURRENT_CONTEXT.lock().unwrap().replace(context.to_string());
-        CLIENT.lock().unwrap().replace(client);
-    }
+async fn get_context_auth_info(context: &str) -> Result<NamedAuthInfo, SerializableKubeError> {
+    let config = kubeconfig::load_kubeconfig()?.kubeconfig;
 
-    return Ok(CLIENT.lock().unwrap().clone().unwrap());
+    return kubeconfig::find_auth_info(&config, context);
 }
 
 #[tauri::command]
@@ -426,7 +336,6 @@ async fn replace_pod(
     return pod_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|pod| pod.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -443,7 +352,6 @@ async fn replace_deployment(
     return deployment_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|deployment| deployment.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -460,7 +368,6 @@ async fn replace_job(
     return job_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|job| job.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -477,7 +384,6 @@ async fn replace_cronjob(
     return cronjob_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|cronjob| cronjob.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -494,7 +400,6 @@ async fn replace_configmap(
     return configmap_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|configmap| configmap.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -511,7 +416,6 @@ async fn replace_secret(
     return secret_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|secret| secret.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -528,7 +432,6 @@ async fn replace_service(
     return service_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|service| service.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -545,7 +448,6 @@ async fn replace_virtualservice(
     return virtualservice_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|virtualservice| virtualservice.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -562,7 +464,6 @@ async fn replace_ingress(
     return ingress_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|ingress| ingress.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -579,7 +480,6 @@ async fn replace_persistentvolumeclaim(
     return pvc_api
         .replace(name, &Default::default(), &object)
         .await
-        .map(|pvc: PersistentVolumeClaim| pvc.clone())
         .map_err(|err| SerializableKubeError::from(err));
 }
 
@@ -633,108 +533,8 @@ async fn get_api_group_resources(
         .map_err(|err| SerializableKubeError::from(err));
 }
 
-struct TerminalSession {
-    writer: Arc<Mutex<Box<dyn Write + Send>>>,
-}
-
-static TTY_SESSIONS: Mutex<Option<HashMap<String, TerminalSession>>> = Mutex::new(None);
-
-#[tauri::command]
-fn create_tty_session(app_handle: tauri::AppHandle, init_command: Vec<String>) -> String {
-    if TTY_SESSIONS.lock().unwrap().is_none() {
-        *TTY_SESSIONS.lock().unwrap() = Some(HashMap::new());
-    }
-
-    let pty_system = native_pty_system();
-    let pty_pair = pty_system
-        .openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
-        })
-        .unwrap();
-
-    // generate a random session id
-    let session_id = Uuid::new_v4().to_string();
-    let thread_session_id = session_id.clone();
-
-    #[cfg(target_os = "windows")]
-    let cmd = CommandBuilder::new("powershell.exe");
-    #[cfg(not(target_os = "windows"))]
-    let cmd = CommandBuilder::from_argv(
-        init_command
-            .into_iter()
-            .map(|s| OsString::from(s))
-            .collect(),
-    );
-
-    let mut child = pty_pair.slave.spawn_command(cmd).unwrap();
-    thread::spawn(move || {
-        child.wait().unwrap();
-    });
-
-    let reader = pty_pair.master.try_clone_reader().unwrap();
-    let reader = Arc::new(Mutex::new(Some(BufReader::new(reader))));
-
-    thread::spawn(move || {
-        let reader = reader.lock().unwrap().take();
-        let app = app_handle.clone();
-        let session_id = thread_session_id.clone();
-        if let Some(mut reader) = reader {
-            loop {
-                sleep(Duration::from_millis(1));
-                let data = reader.fill_buf().unwrap().to_vec();
-                reader.consume(data.len());
-                if data.len() > 0 {
-                    app.emit_all(format!("tty_data_{}", session_id).as_ref(), data)
-                        .unwrap();
-                }
-            }
-        }
-    });
-
-    let writer = pty_pair.master.take_writer().unwrap();
-    TTY_SESSIONS.lock().unwrap().as_mut().unwrap().insert(
-        session_id.clone(),
-        TerminalSession {
-            writer: Arc::new(Mutex::new(writer)),
-        },
-    );
-
-    return session_id;
-}
-
-#[tauri::command]
-fn stop_tty_session(session_id: &str) {
-    // write to pty to kill the process, this can be a bash or powershell command
-    write_to_pty(session_id, "exit\n");
-}
-
-#[tauri::command]
-fn write_to_pty(session_id: &str, data: &str) {
-    // First, lock the sessions map
-    let sessions_lock = TTY_SESSIONS.lock().unwrap();
-
-    // Then, try to get the session from the map
-    if let Some(sessions) = sessions_lock.as_ref() {
-        if let Some(session) = sessions.get(session_id) {
-            // Lock the writer
-            let mut writer_guard = session.writer.lock().unwrap();
-            // Attempt to write and handle any error
-            if let Err(_) = write!(&mut *writer_guard, "{}", data) {
-                // Handle the error from the write! macro here
-            }
-        } else {
-            // Handle the case when the session is not found
-        }
-    } else {
-        // Handle the case when the TTY_SESSIONS map is not initialized
-    }
-}
-
 fn main() {
-    let _ = fix_path_env::fix();
+    shell_env::fix_path();
 
     let metadata = AboutMetadata::new()
         .authors(vec!["@unxsist".to_string()])
@@ -759,17 +559,21 @@ fn main() {
     tauri::Builder::default()
         .menu(menu)
         .on_menu_event(|event| {
-            match event.menu_item_id() {
-                "check_for_updates" => {
-                    event.window().emit("check_for_updates", CheckForUpdatesPayload {}).unwrap();
-                }
-                _ => {}
-              }
+            if event.menu_item_id() == "check_for_updates" {
+                event.window().emit("check_for_updates", CheckForUpdatesPayload {}).unwrap();
+            }
         })
         .invoke_handler(tauri::generate_handler![
             list_contexts,
+            kubeconfig::list_context_sources,
             get_context_auth_info,
+            credentials::get_login_provider,
+            credentials::refresh_credentials,
+            oidc::login_oidc,
+            oidc::cancel_oidc_login,
+            oidc::logout_oidc,
             get_current_context,
+            client_pool::reset_client,
             list_namespaces,
             get_core_api_versions,
             get_core_api_resources,
@@ -780,6 +584,29 @@ fn main() {
             delete_pod,
             list_deployments,
             restart_deployment,
+            rollout::get_rollout_history,
+            rollout::undo_rollout,
+            rollout::pause_rollout,
+            rollout::resume_rollout,
+            rollout::watch_rollout_status,
+            workloads::list_statefulsets,
+            workloads::get_statefulset,
+            workloads::replace_statefulset,
+            workloads::restart_statefulset,
+            workloads::scale_statefulset,
+            workloads::set_statefulset_partition,
+            workloads::watch_statefulset_rollout_status,
+            workloads::list_daemonsets,
+            workloads::get_daemonset,
+            workloads::replace_daemonset,
+            workloads::restart_daemonset,
+            workloads::watch_daemonset_rollout_status,
+            workloads::list_replicasets,
+            workloads::get_replicaset,
+            workloads::replace_replicaset,
+            workloads::restart_replicaset,
+            workloads::scale_replicaset,
+            workloads::watch_replicaset_rollout_status,
             list_jobs,
             list_cronjobs,
             list_configmaps,
@@ -800,19 +627,62 @@ fn main() {
             replace_ingress,
             replace_persistentvolumeclaim,
             get_pod_metrics,
-            create_tty_session,
-            stop_tty_session,
-            write_to_pty
+            nodes::list_nodes,
+            nodes::get_node_metrics,
+            nodes::cordon_node,
+            nodes::uncordon_node,
+            nodes::drain_node,
+            watch::watch_namespaces,
+            watch::watch_pods,
+            watch::watch_deployments,
+            watch::watch_jobs,
+            watch::watch_cronjobs,
+            watch::watch_configmaps,
+            watch::watch_secrets,
+            watch::watch_services,
+            watch::watch_virtual_services,
+            watch::watch_ingresses,
+            watch::watch_persistentvolumes,
+            watch::watch_persistentvolumeclaims,
+            watch::watch_resource,
+            watch::unwatch,
+            events::list_events,
+            events::watch_events,
+            describe::describe_resource,
+            logs::stream_logs,
+            logs::stream_aggregated_logs,
+            logs::stop_logs,
+            log_export::export_logs,
+            dynamic::list_resource,
+            dynamic::get_resource,
+            dynamic::replace_resource,
+            dynamic::patch_resource,
+            dynamic::apply_resource,
+            dynamic::preview_change,
+            dynamic::scale_resource,
+            dynamic::delete_resource,
+            tty::create_tty_session,
+            tty::create_exec_session,
+            tty::stop_tty_session,
+            tty::kill_tty_session,
+            tty::list_tty_sessions,
+            tty::write_to_pty,
+            tty::resize_tty
         ])
         .setup(|_app| {
             let _window = _app.get_window("main").unwrap();
 
+            if let Some(config_dir) = _app.path_resolver().app_config_dir() {
+                kubeconfig::set_settings_file(config_dir.join("settings.json"));
+                oidc::set_token_file(config_dir.join("oidc_tokens.json"));
+            }
+            kubeconfig::watch_kubeconfig(_app.handle());
+
             #[cfg(target_os = "macos")]
             {
-                use tauri_nspanel::cocoa;
-                use tauri_nspanel::cocoa::appkit::NSWindow;
-                use tauri_nspanel::cocoa::appkit::NSWindowTitleVisibility;
-                use tauri_nspanel::cocoa::base::BOOL;
+                use cocoa::appkit::NSWindow;
+                use cocoa::appkit::NSWindowTitleVisibility;
+                use cocoa::base::BOOL;
 
                 unsafe {
                     let id = _window.ns_window().unwrap() as cocoa::base::id;
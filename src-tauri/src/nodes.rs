@@ -0,0 +1,427 @@
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use futures::StreamExt;
+use k8s_metrics::v1beta1::NodeMetrics;
+use k8s_openapi::api::core::v1::{Node, Pod};
+use k8s_openapi::api::policy::v1::PodDisruptionBudget;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::api::{Api, DeleteParams, EvictParams, ListParams};
+use kube::ResourceExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+const EVICTION_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const MAX_CONCURRENT_EVICTIONS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum DrainPodStatus {
+    Skipped,
+    Evicting,
+    Blocked,
+    Evicted,
+    Failed,
+}
+
+/// Payload of the `drain_progress_<subscription_id>` events, one per change
+/// in the state of a pod on the node.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainProgress {
+    pub namespace: String,
+    pub name: String,
+    pub status: DrainPodStatus,
+    pub reason: Option<String>,
+    pub disruption_budget: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainResult {
+    pub evicted: Vec<DrainProgress>,
+    pub skipped: Vec<DrainProgress>,
+    pub blocked: Vec<DrainProgress>,
+    pub failed: Vec<DrainProgress>,
+}
+
+#[tauri::command]
+pub async fn list_nodes(context: &str) -> Result<Vec<Node>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let node_api: Api<Node> = Api::all(client);
+
+    return node_api
+        .list(&ListParams::default())
+        .await
+        .map(|nodes| nodes.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn get_node_metrics(context: &str) -> Result<Vec<NodeMetrics>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let metrics_api: Api<NodeMetrics> = Api::all(client);
+
+    return metrics_api
+        .list(&ListParams::default())
+        .await
+        .map(|metrics| metrics.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn cordon_node(context: &str, name: &str) -> Result<Node, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let node_api: Api<Node> = Api::all(client);
+
+    return node_api
+        .cordon(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn uncordon_node(context: &str, name: &str) -> Result<Node, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let node_api: Api<Node> = Api::all(client);
+
+    return node_api
+        .uncordon(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+/// Evaluates a label selector, including its match expressions, against a
+/// set of labels. An empty selector matches everything, like the api server.
+pub fn selector_matches(selector: &LabelSelector, labels: &BTreeMap<String, String>) -> bool {
+    let labels_match = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key) == Some(value));
+
+    let expressions_match = selector.match_expressions.iter().flatten().all(|expression| {
+        let values = expression.values.clone().unwrap_or_default();
+        let value = labels.get(&expression.key);
+
+        match expression.operator.as_str() {
+            "In" => value.is_some_and(|value| values.contains(value)),
+            "NotIn" => value.is_none_or(|value| !values.contains(value)),
+            "Exists" => value.is_some(),
+            "DoesNotExist" => value.is_none(),
+            _ => false,
+        }
+    });
+
+    return labels_match && expressions_match;
+}
+
+/// Why a pod is left alone by a drain, following `kubectl drain`'s defaults.
+fn skip_reason(pod: &Pod) -> Option<String> {
+    if pod.annotations().contains_key(MIRROR_POD_ANNOTATION) {
+        return Some("Mirror pod, managed by the kubelet".to_string());
+    }
+
+    let managed_by_daemonset = pod
+        .owner_references()
+        .iter()
+        .any(|owner| owner.controller == Some(true) && owner.kind == "DaemonSet");
+    if managed_by_daemonset {
+        return Some("Managed by a DaemonSet".to_string());
+    }
+
+    return None;
+}
+
+/// Why evicting a pod would lose something, which makes the whole drain fail
+/// unless `force` or `delete_emptydir_data` accept the loss.
+fn blocking_reason(pod: &Pod, force: bool, delete_emptydir_data: bool) -> Option<String> {
+    let finished = matches!(
+        pod.status.as_ref().and_then(|status| status.phase.as_deref()),
+        Some("Succeeded") | Some("Failed")
+    );
+    if finished {
+        return None;
+    }
+
+    let managed = pod.owner_references().iter().any(|owner| owner.controller == Some(true));
+    if !managed && !force {
+        return Some("Not managed by a controller, it would not be recreated".to_string());
+    }
+
+    let uses_emptydir = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.volumes.as_ref())
+        .is_some_and(|volumes| volumes.iter().any(|volume| volume.empty_dir.is_some()));
+    if uses_emptydir && !delete_emptydir_data {
+        return Some("Uses emptyDir storage, which would be lost".to_string());
+    }
+
+    return None;
+}
+
+fn progress(pod: &Pod, status: DrainPodStatus, reason: Option<String>) -> DrainProgress {
+    return DrainProgress {
+        namespace: pod.namespace().unwrap_or_default(),
+        name: pod.name_any(),
+        status,
+        reason,
+        disruption_budget: None,
+    };
+}
+
+/// Finds the disruption budget that covers `pod`, to tell the user which one
+/// refused the eviction.
+fn disruption_budget_for(budgets: &[PodDisruptionBudget], pod: &Pod) -> Option<String> {
+    return budgets
+        .iter()
+        .filter(|budget| budget.namespace() == pod.namespace())
+        .find(|budget| {
+            budget
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.selector.as_ref())
+                .is_some_and(|selector| selector_matches(selector, pod.labels()))
+        })
+        .map(|budget| budget.name_any());
+}
+
+/// Evicts `pod`, retrying while a disruption budget refuses the eviction until
+/// `deadline`. A 429 is how the Eviction API reports a budget violation.
+async fn evict_pod(
+    app_handle: &tauri::AppHandle,
+    event_name: &str,
+    client: kube::Client,
+    budgets: &[PodDisruptionBudget],
+    pod: &Pod,
+    grace_period_seconds: Option<u32>,
+    deadline: Instant,
+) -> DrainProgress {
+    let namespace = pod.namespace().unwrap_or_default();
+    let pod_api: Api<Pod> = Api::namespaced(client, &namespace);
+
+    let evict_params = EvictParams {
+        delete_options: grace_period_seconds.map(|seconds| DeleteParams::default().grace_period(seconds)),
+        ..EvictParams::default()
+    };
+
+    let mut reported_block = false;
+    loop {
+        match pod_api.evict(&pod.name_any(), &evict_params).await {
+            Ok(_) => break,
+            Err(kube::Error::Api(error)) if error.code == 404 => break,
+            Err(kube::Error::Api(error)) if error.code == 429 => {
+                let mut blocked = progress(pod, DrainPodStatus::Blocked, Some(error.message));
+                blocked.disruption_budget = disruption_budget_for(budgets, pod);
+
+                if Instant::now() >= deadline {
+                    return blocked;
+                }
+                if !reported_block {
+                    let _ = app_handle.emit_all(event_name, blocked);
+                    reported_block = true;
+                }
+                tokio::time::sleep(EVICTION_RETRY_INTERVAL).await;
+            }
+            Err(err) => {
+                return progress(
+                    pod,
+                    DrainPodStatus::Failed,
+                    Some(SerializableKubeError::from(err).message),
+                )
+            }
+        }
+    }
+
+    // The eviction only starts a graceful delete, the pod is gone once it no
+    // longer exists or has been replaced by one with the same name.
+    while Instant::now() < deadline {
+        match pod_api.get_opt(&pod.name_any()).await {
+            Ok(Some(current)) if current.uid() == pod.uid() => {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            Ok(_) => return progress(pod, DrainPodStatus::Evicted, None),
+            Err(err) => {
+                return progress(
+                    pod,
+                    DrainPodStatus::Failed,
+                    Some(SerializableKubeError::from(err).message),
+                )
+            }
+        }
+    }
+
+    return progress(
+        pod,
+        DrainPodStatus::Failed,
+        Some("Timed out waiting for the pod to terminate".to_string()),
+    );
+}
+
+/// Cordons the node and evicts its pods through the Eviction API, so
+/// PodDisruptionBudgets are respected. DaemonSet and mirror pods are
+/// skipped. Like `kubectl drain`, the drain fails before evicting anything
+/// when there are unmanaged pods without `force` or emptyDir pods without
+/// `delete_emptydir_data`, listing them in the error details; the node stays
+/// cordoned. Pods whose budget still blocks the eviction after
+/// `timeout_seconds` are returned in `blocked`.
+#[tauri::command]
+pub async fn drain_node(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    name: &str,
+    force: bool,
+    delete_emptydir_data: bool,
+    grace_period_seconds: Option<u32>,
+    timeout_seconds: Option<u64>,
+) -> Result<DrainResult, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let node_api: Api<Node> = Api::all(client.clone());
+    let pod_api: Api<Pod> = Api::all(client.clone());
+
+    let event_name = format!("drain_progress_{}", subscription_id);
+    let deadline = Instant::now()
+        + timeout_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+
+    node_api
+        .cordon(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let pods = pod_api
+        .list(&ListParams::default().fields(&format!("spec.nodeName={}", name)))
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let mut result = DrainResult {
+        evicted: Vec::new(),
+        skipped: Vec::new(),
+        blocked: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    let (skipped, to_evict): (Vec<Pod>, Vec<Pod>) =
+        pods.items.into_iter().partition(|pod| skip_reason(pod).is_some());
+
+    let blocking: Vec<DrainProgress> = to_evict
+        .iter()
+        .filter_map(|pod| {
+            blocking_reason(pod, force, delete_emptydir_data)
+                .map(|reason| progress(pod, DrainPodStatus::Failed, Some(reason)))
+        })
+        .collect();
+    if !blocking.is_empty() {
+        let pods = blocking
+            .iter()
+            .map(|pod| format!("{}/{} ({})", pod.namespace, pod.name, pod.reason.clone().unwrap_or_default()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        return Err(SerializableKubeError::new(
+            ErrorKind::Invalid,
+            format!("Cannot drain node {}, these pods would be lost: {}", name, pods),
+        )
+        .with_details(serde_json::json!({ "pods": blocking })));
+    }
+
+    for pod in &skipped {
+        let skipped = progress(pod, DrainPodStatus::Skipped, skip_reason(pod));
+        let _ = app_handle.emit_all(&event_name, skipped.clone());
+        result.skipped.push(skipped);
+    }
+
+    // Only used to name the budget blocking an eviction, so a failure to list
+    // them doesn't fail the drain.
+    let budget_api: Api<PodDisruptionBudget> = Api::all(client.clone());
+    let budgets = budget_api
+        .list(&ListParams::default())
+        .await
+        .map(|budgets| budgets.items)
+        .unwrap_or_default();
+
+    let evictions = futures::stream::iter(to_evict.iter().map(|pod| {
+        let app_handle = app_handle.clone();
+        let event_name = event_name.clone();
+        let client = client.clone();
+        let budgets = &budgets;
+
+        async move {
+            let _ = app_handle.emit_all(&event_name, progress(pod, DrainPodStatus::Evicting, None));
+            let outcome =
+                evict_pod(&app_handle, &event_name, client, budgets, pod, grace_period_seconds, deadline).await;
+            let _ = app_handle.emit_all(&event_name, outcome.clone());
+            outcome
+        }
+    }))
+    .buffer_unordered(MAX_CONCURRENT_EVICTIONS)
+    .collect::<Vec<DrainProgress>>()
+    .await;
+
+    for outcome in evictions {
+        match outcome.status {
+            DrainPodStatus::Evicted => result.evicted.push(outcome),
+            DrainPodStatus::Blocked => result.blocked.push(outcome),
+            _ => result.failed.push(outcome),
+        }
+    }
+
+    return Ok(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(owner_kind: Option<&str>, emptydir: bool, phase: &str) -> Pod {
+        let owners: Vec<serde_json::Value> = owner_kind
+            .iter()
+            .map(|kind| {
+                serde_json::json!({
+                    "apiVersion": "apps/v1",
+                    "kind": kind,
+                    "name": "owner",
+                    "uid": "owner-uid",
+                    "controller": true,
+                })
+            })
+            .collect();
+        let volumes: Vec<serde_json::Value> = if emptydir {
+            vec![serde_json::json!({ "name": "cache", "emptyDir": {} })]
+        } else {
+            Vec::new()
+        };
+
+        return serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web-0", "namespace": "default", "ownerReferences": owners },
+            "spec": { "containers": [{ "name": "web" }], "volumes": volumes },
+            "status": { "phase": phase },
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn daemonset_pods_are_skipped() {
+        assert!(skip_reason(&pod(Some("DaemonSet"), false, "Running")).is_some());
+        assert!(skip_reason(&pod(Some("ReplicaSet"), false, "Running")).is_none());
+    }
+
+    #[test]
+    fn unmanaged_pods_block_unless_forced() {
+        let unmanaged = pod(None, false, "Running");
+        assert!(blocking_reason(&unmanaged, false, false).is_some());
+        assert!(blocking_reason(&unmanaged, true, false).is_none());
+        assert!(blocking_reason(&pod(None, false, "Succeeded"), false, false).is_none());
+    }
+
+    #[test]
+    fn emptydir_pods_block_unless_data_may_be_deleted() {
+        let cached = pod(Some("ReplicaSet"), true, "Running");
+        assert!(blocking_reason(&cached, true, false).is_some());
+        assert!(blocking_reason(&cached, false, true).is_none());
+    }
+}
@@ -0,0 +1,1117 @@
+use crate::client_pool::invalidate_client;
+use crate::kubeconfig::{arg_value, arg_values, find_auth_info, load_kubeconfig, read_settings};
+use crate::{ErrorKind, SerializableKubeError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use kube::config::AuthInfo;
+use rand::RngCore;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::Manager;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// kubelogin's default, so redirect uris registered for it keep working.
+const DEFAULT_LOOPBACK_PORT: u16 = 8000;
+const LOGIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+/// How much a `slow_down` response adds to the device code polling interval.
+const SLOW_DOWN: std::time::Duration = std::time::Duration::from_secs(5);
+
+static TOKEN_FILE: OnceLock<PathBuf> = OnceLock::new();
+static TOKENS: Mutex<Option<HashMap<String, StoredTokens>>> = Mutex::new(None);
+static PROVIDERS: Mutex<Option<HashMap<String, ProviderMetadata>>> = Mutex::new(None);
+
+/// Every request of a client asks for a token, so many of them can find it
+/// expired at once. Only one refreshes it, which matters for providers that
+/// rotate refresh tokens.
+static REFRESH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Cancels the login in progress. Starting a new login cancels the previous
+/// one, which also frees the loopback port it was listening on.
+static PENDING_LOGIN: Mutex<Option<Arc<Notify>>> = Mutex::new(None);
+static LOOPBACK_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OidcFlow {
+    AuthorizationCode,
+    DeviceCode,
+}
+
+/// Where the OIDC settings of a kubeconfig user came from. Only exec plugins
+/// have something to fall back to when we hold no tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OidcSource {
+    AuthProvider,
+    Kubelogin,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OidcConfig {
+    pub source: OidcSource,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    pub flow: OidcFlow,
+    pub loopback_port: u16,
+    pub id_token: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredTokens {
+    id_token: String,
+    refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OidcToken {
+    pub id_token: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OidcLoginPromptPayload {
+    context: String,
+    url: String,
+    user_code: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+struct ProviderMetadata {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    interval: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+    refresh_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+pub fn set_token_file(token_file: PathBuf) {
+    let _ = TOKEN_FILE.set(token_file);
+}
+
+fn with_openid_scope(extra_scopes: Vec<String>) -> Vec<String> {
+    let mut scopes = vec!["openid".to_string()];
+    for scope in extra_scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    return scopes;
+}
+
+/// Reads the OIDC settings from an `auth-provider: oidc` entry or from the
+/// arguments of a kubelogin (`kubectl oidc-login get-token`) exec plugin.
+pub fn oidc_config(auth_info: &AuthInfo) -> Option<OidcConfig> {
+    if let Some(provider) = auth_info.auth_provider.as_ref().filter(|provider| provider.name == "oidc") {
+        let config = &provider.config;
+        let extra_scopes = config
+            .get("extra-scopes")
+            .map(|scopes| scopes.split(',').map(|scope| scope.trim().to_string()).collect())
+            .unwrap_or_default();
+
+        return Some(OidcConfig {
+            source: OidcSource::AuthProvider,
+            issuer_url: config.get("idp-issuer-url")?.clone(),
+            client_id: config.get("client-id")?.clone(),
+            client_secret: config.get("client-secret").cloned(),
+            scopes: with_openid_scope(extra_scopes),
+            flow: OidcFlow::AuthorizationCode,
+            loopback_port: DEFAULT_LOOPBACK_PORT,
+            id_token: config.get("id-token").cloned(),
+            refresh_token: config.get("refresh-token").cloned(),
+        });
+    }
+
+    let exec = auth_info.exec.as_ref()?;
+    let command = exec.command.as_ref()?;
+    let args = exec.args.clone().unwrap_or_default();
+    let binary = Path::new(command).file_stem()?.to_string_lossy().to_string();
+
+    let is_kubelogin = match binary.as_str() {
+        "kubectl-oidc_login" | "kubelogin" => args.first().map(String::as_str) == Some("get-token"),
+        "kubectl" => {
+            args.first().map(String::as_str) == Some("oidc-login")
+                && args.get(1).map(String::as_str) == Some("get-token")
+        }
+        _ => false,
+    };
+    if !is_kubelogin {
+        return None;
+    }
+
+    let flow = match arg_value(&args, "--grant-type").as_deref() {
+        Some("device-code") => OidcFlow::DeviceCode,
+        _ => OidcFlow::AuthorizationCode,
+    };
+    let loopback_port = arg_value(&args, "--listen-address")
+        .and_then(|address| address.rsplit(':').next()?.parse().ok())
+        .unwrap_or(DEFAULT_LOOPBACK_PORT);
+
+    return Some(OidcConfig {
+        source: OidcSource::Kubelogin,
+        issuer_url: arg_value(&args, "--oidc-issuer-url")?,
+        client_id: arg_value(&args, "--oidc-client-id")?,
+        client_secret: arg_value(&args, "--oidc-client-secret"),
+        scopes: with_openid_scope(arg_values(&args, "--oidc-extra-scope")),
+        flow,
+        loopback_port,
+        id_token: None,
+        refresh_token: None,
+    });
+}
+
+/// Reads the `exp` claim of an id token. The api server verifies the
+/// signature, we only need to know when to refresh.
+fn id_token_expiry(id_token: &str) -> Option<DateTime<Utc>> {
+    let payload = id_token.split('.').nth(1)?;
+    let claims: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+
+    return Utc.timestamp_opt(claims["exp"].as_i64()?, 0).single();
+}
+
+fn load_tokens() -> HashMap<String, StoredTokens> {
+    return TOKEN_FILE
+        .get()
+        .and_then(|token_file| fs::read_to_string(token_file).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+}
+
+fn save_tokens(tokens: &HashMap<String, StoredTokens>) -> Result<(), SerializableKubeError> {
+    let token_file = match TOKEN_FILE.get() {
+        Some(token_file) => token_file,
+        None => return Ok(()),
+    };
+
+    let contents = serde_json::to_string_pretty(tokens)
+        .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    return options
+        .open(token_file)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|err| {
+            SerializableKubeError::new(
+                ErrorKind::Unknown,
+                format!("Unable to store OIDC tokens in {:?}: {}", token_file, err),
+            )
+        });
+}
+
+fn stored_tokens(context: &str) -> Option<StoredTokens> {
+    let mut tokens = TOKENS.lock().unwrap();
+
+    return tokens.get_or_insert_with(load_tokens).get(context).cloned();
+}
+
+fn store_tokens(context: &str, stored: Option<StoredTokens>) -> Result<(), SerializableKubeError> {
+    let mut tokens = TOKENS.lock().unwrap();
+    let tokens = tokens.get_or_insert_with(load_tokens);
+
+    match stored {
+        Some(stored) => tokens.insert(context.to_string(), stored),
+        None => tokens.remove(context),
+    };
+
+    return save_tokens(tokens);
+}
+
+fn http_error(err: reqwest::Error) -> SerializableKubeError {
+    let kind = if err.is_timeout() {
+        ErrorKind::Timeout
+    } else if err.is_connect() {
+        ErrorKind::ConnectionRefused
+    } else {
+        ErrorKind::ExecPluginFailed
+    };
+
+    return SerializableKubeError::new(kind, err.to_string());
+}
+
+async fn discover(issuer_url: &str) -> Result<ProviderMetadata, SerializableKubeError> {
+    if let Some(metadata) = PROVIDERS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|providers| providers.get(issuer_url))
+    {
+        return Ok(metadata.clone());
+    }
+
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer_url.trim_end_matches('/')
+    );
+
+    let metadata: ProviderMetadata = reqwest::get(url)
+        .await
+        .map_err(http_error)?
+        .error_for_status()
+        .map_err(http_error)?
+        .json()
+        .await
+        .map_err(http_error)?;
+
+    PROVIDERS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(issuer_url.to_string(), metadata.clone());
+
+    return Ok(metadata);
+}
+
+async fn request_token(
+    config: &OidcConfig,
+    token_endpoint: &str,
+    mut form: Vec<(&str, String)>,
+) -> Result<TokenResponse, SerializableKubeError> {
+    form.push(("client_id", config.client_id.clone()));
+    if let Some(client_secret) = &config.client_secret {
+        form.push(("client_secret", client_secret.clone()));
+    }
+
+    // Token endpoints report failures like `authorization_pending` as a 400
+    // with a json body, so the body is parsed regardless of the status.
+    return reqwest::Client::new()
+        .post(token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(http_error)?
+        .json()
+        .await
+        .map_err(http_error);
+}
+
+fn token_error(response: &TokenResponse) -> SerializableKubeError {
+    let error = response.error.clone().unwrap_or_default();
+    let kind = match error.as_str() {
+        "invalid_grant" | "expired_token" | "access_denied" => ErrorKind::AuthExpired,
+        _ => ErrorKind::ExecPluginFailed,
+    };
+
+    return SerializableKubeError::new(
+        kind,
+        format!(
+            "OIDC token request failed: {} {}",
+            error,
+            response.error_description.clone().unwrap_or_default()
+        )
+        .trim()
+        .to_string(),
+    );
+}
+
+fn into_stored_tokens(
+    response: TokenResponse,
+    previous_refresh_token: Option<String>,
+) -> Result<StoredTokens, SerializableKubeError> {
+    if response.error.is_some() {
+        return Err(token_error(&response));
+    }
+
+    let id_token = response.id_token.ok_or(SerializableKubeError::new(
+        ErrorKind::ExecPluginFailed,
+        "The token response did not include an id_token".to_string(),
+    ))?;
+    let expires_at = id_token_expiry(&id_token).ok_or(SerializableKubeError::new(
+        ErrorKind::ExecPluginFailed,
+        "Unable to read the expiry of the id_token".to_string(),
+    ))?;
+
+    return Ok(StoredTokens {
+        id_token,
+        // Providers that don't rotate refresh tokens leave them out of the response.
+        refresh_token: response.refresh_token.or(previous_refresh_token),
+        expires_at,
+    });
+}
+
+async fn refresh(config: &OidcConfig, refresh_token: &str) -> Result<StoredTokens, SerializableKubeError> {
+    let metadata = discover(&config.issuer_url).await?;
+    let response = request_token(
+        config,
+        &metadata.token_endpoint,
+        vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token.to_string()),
+        ],
+    )
+    .await?;
+
+    return into_stored_tokens(response, Some(refresh_token.to_string()));
+}
+
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+
+    return URL_SAFE_NO_PAD.encode(bytes);
+}
+
+fn prompt(app_handle: &tauri::AppHandle, context: &str, url: String, user_code: Option<String>) {
+    let _ = app_handle.emit_all(
+        "oidc_login_prompt",
+        OidcLoginPromptPayload {
+            context: context.to_string(),
+            url,
+            user_code,
+        },
+    );
+}
+
+fn cancelled_error() -> SerializableKubeError {
+    return SerializableKubeError::new(ErrorKind::Cancelled, "The login was cancelled".to_string());
+}
+
+/// Polls the token endpoint until the user approved the device code, backing
+/// off when the provider asks to slow down.
+async fn poll_device_token(
+    config: &OidcConfig,
+    token_endpoint: &str,
+    device_code: &str,
+    mut interval: std::time::Duration,
+    expires_in: std::time::Duration,
+    cancel: &Notify,
+) -> Result<StoredTokens, SerializableKubeError> {
+    let deadline = tokio::time::Instant::now() + expires_in;
+
+    while tokio::time::Instant::now() < deadline {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = cancel.notified() => return Err(cancelled_error()),
+        }
+
+        let response = request_token(
+            config,
+            token_endpoint,
+            vec![
+                (
+                    "grant_type",
+                    "urn:ietf:params:oauth:grant-type:device_code".to_string(),
+                ),
+                ("device_code", device_code.to_string()),
+            ],
+        )
+        .await?;
+
+        match response.error.as_deref() {
+            Some("authorization_pending") => continue,
+            Some("slow_down") => interval += SLOW_DOWN,
+            _ => return into_stored_tokens(response, None),
+        }
+    }
+
+    return Err(SerializableKubeError::new(
+        ErrorKind::Timeout,
+        "The device code expired before the login was completed".to_string(),
+    ));
+}
+
+async fn device_code_login(
+    app_handle: &tauri::AppHandle,
+    context: &str,
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    cancel: &Notify,
+) -> Result<StoredTokens, SerializableKubeError> {
+    let device_endpoint = metadata.device_authorization_endpoint.as_ref().ok_or(
+        SerializableKubeError::new(
+            ErrorKind::ExecPluginFailed,
+            format!("{} does not support the device code flow", config.issuer_url),
+        ),
+    )?;
+
+    let mut form = vec![
+        ("client_id", config.client_id.clone()),
+        ("scope", config.scopes.join(" ")),
+    ];
+    if let Some(client_secret) = &config.client_secret {
+        form.push(("client_secret", client_secret.clone()));
+    }
+
+    let authorization: DeviceAuthorization = reqwest::Client::new()
+        .post(device_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(http_error)?
+        .error_for_status()
+        .map_err(http_error)?
+        .json()
+        .await
+        .map_err(http_error)?;
+
+    prompt(
+        app_handle,
+        context,
+        authorization
+            .verification_uri_complete
+            .clone()
+            .unwrap_or(authorization.verification_uri.clone()),
+        Some(authorization.user_code.clone()),
+    );
+
+    return poll_device_token(
+        config,
+        &metadata.token_endpoint,
+        &authorization.device_code,
+        std::time::Duration::from_secs(authorization.interval.unwrap_or(5)),
+        std::time::Duration::from_secs(authorization.expires_in),
+        cancel,
+    )
+    .await;
+}
+
+/// Answers a request to the loopback listener, returning its query parameters
+/// when it is the login redirect.
+async fn read_redirect(stream: tokio::net::TcpStream) -> Option<HashMap<String, String>> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    let _ = reader.read_line(&mut request_line).await;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    let params: HashMap<String, String> = match Url::parse(&format!("http://localhost{}", path)) {
+        Ok(url) => url.query_pairs().into_owned().collect(),
+        Err(_) => HashMap::new(),
+    };
+
+    // Browsers also ask for things like /favicon.ico, which are ignored.
+    if !params.contains_key("code") && !params.contains_key("error") {
+        let _ = reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+            .await;
+        return None;
+    }
+
+    let body = "<html><body>You are logged in, you can close this window and return to JET Pilot.</body></html>";
+    let _ = reader
+        .get_mut()
+        .write_all(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await;
+
+    return Some(params);
+}
+
+/// Waits for the browser to be redirected back to the loopback listener and
+/// returns the query parameters of that request.
+async fn wait_for_redirect(
+    listener: TcpListener,
+    cancel: &Notify,
+) -> Result<HashMap<String, String>, SerializableKubeError> {
+    let timeout = tokio::time::sleep(LOGIN_TIMEOUT);
+    tokio::pin!(timeout);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => return Err(SerializableKubeError::new(ErrorKind::Unknown, err.to_string())),
+            },
+            _ = cancel.notified() => return Err(cancelled_error()),
+            _ = &mut timeout => {
+                return Err(SerializableKubeError::new(
+                    ErrorKind::Timeout,
+                    "Timed out waiting for the browser login to complete".to_string(),
+                ))
+            }
+        };
+
+        if let Some(params) = read_redirect(stream).await {
+            return Ok(params);
+        }
+    }
+}
+
+fn pkce_challenge(code_verifier: &str) -> String {
+    return URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+}
+
+/// Checks the redirect belongs to the login we started and returns its code.
+fn redirect_code(params: &HashMap<String, String>, state: &str) -> Result<String, SerializableKubeError> {
+    if let Some(error) = params.get("error") {
+        return Err(SerializableKubeError::new(
+            ErrorKind::AuthExpired,
+            format!(
+                "Login was rejected: {} {}",
+                error,
+                params.get("error_description").cloned().unwrap_or_default()
+            )
+            .trim()
+            .to_string(),
+        ));
+    }
+    if params.get("state").map(String::as_str) != Some(state) {
+        return Err(SerializableKubeError::new(
+            ErrorKind::ExecPluginFailed,
+            "The login redirect had an unexpected state".to_string(),
+        ));
+    }
+
+    return params.get("code").cloned().ok_or(SerializableKubeError::new(
+        ErrorKind::ExecPluginFailed,
+        "The login redirect did not include a code".to_string(),
+    ));
+}
+
+async fn authorization_code_login(
+    app_handle: &tauri::AppHandle,
+    context: &str,
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    cancel: &Notify,
+) -> Result<StoredTokens, SerializableKubeError> {
+    let redirect_uri = format!("http://localhost:{}", config.loopback_port);
+
+    let state = random_string();
+    let code_verifier = random_string();
+    let code_challenge = pkce_challenge(&code_verifier);
+
+    let authorization_url = Url::parse_with_params(
+        &metadata.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", config.scopes.join(" ").as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|err| SerializableKubeError::new(ErrorKind::ExecPluginFailed, err.to_string()))?;
+
+    let params = {
+        // Held until a cancelled login has let go of the port.
+        let _loopback = LOOPBACK_LOCK.lock().await;
+        let listener = TcpListener::bind(("127.0.0.1", config.loopback_port))
+            .await
+            .map_err(|err| {
+                SerializableKubeError::new(
+                    ErrorKind::ExecPluginFailed,
+                    format!(
+                        "Unable to listen on port {} for the login redirect: {}",
+                        config.loopback_port, err
+                    ),
+                )
+            })?;
+
+        prompt(app_handle, context, authorization_url.to_string(), None);
+        wait_for_redirect(listener, cancel).await?
+    };
+    let code = redirect_code(&params, &state)?;
+
+    let response = request_token(
+        config,
+        &metadata.token_endpoint,
+        vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", code_verifier),
+        ],
+    )
+    .await?;
+
+    return into_stored_tokens(response, None);
+}
+
+/// Cancels the login in progress, if any.
+fn cancel_login() {
+    if let Some(pending) = PENDING_LOGIN.lock().unwrap().take() {
+        pending.notify_one();
+    }
+}
+
+/// The login flow and redirect port set for a context in the cluster
+/// settings. `auth-provider` entries carry neither, and kubelogin arguments
+/// can be overridden the same way.
+fn login_settings(context: &str) -> (Option<OidcFlow>, Option<u16>) {
+    let settings = match read_settings() {
+        Ok(settings) => settings,
+        Err(_) => return (None, None),
+    };
+    let context_settings = settings["contextSettings"]
+        .as_array()
+        .and_then(|contexts| contexts.iter().find(|settings| settings["context"] == context))
+        .cloned()
+        .unwrap_or_default();
+
+    return (
+        serde_json::from_value(context_settings["oidcFlow"].clone()).ok(),
+        context_settings["oidcLoopbackPort"]
+            .as_u64()
+            .and_then(|port| u16::try_from(port).ok()),
+    );
+}
+
+/// Logs in through the browser, stores the tokens for `context` and drops its
+/// pooled client so the next request uses them.
+pub async fn login(
+    app_handle: &tauri::AppHandle,
+    context: &str,
+    config: &OidcConfig,
+    flow: Option<OidcFlow>,
+) -> Result<DateTime<Utc>, SerializableKubeError> {
+    let (flow_setting, port_setting) = login_settings(context);
+    let mut config = config.clone();
+    if let Some(port) = port_setting {
+        config.loopback_port = port;
+    }
+    let flow = flow.or(flow_setting).unwrap_or(config.flow);
+
+    let cancel = Arc::new(Notify::new());
+    if let Some(previous) = PENDING_LOGIN.lock().unwrap().replace(cancel.clone()) {
+        previous.notify_one();
+    }
+
+    let metadata = discover(&config.issuer_url).await?;
+    let stored = match flow {
+        OidcFlow::DeviceCode => device_code_login(app_handle, context, &config, &metadata, &cancel).await,
+        OidcFlow::AuthorizationCode => {
+            authorization_code_login(app_handle, context, &config, &metadata, &cancel).await
+        }
+    };
+
+    {
+        let mut pending = PENDING_LOGIN.lock().unwrap();
+        if pending.as_ref().is_some_and(|pending| Arc::ptr_eq(pending, &cancel)) {
+            *pending = None;
+        }
+    }
+
+    let stored = stored?;
+    let expires_at = stored.expires_at;
+
+    store_tokens(context, Some(stored))?;
+    invalidate_client(context);
+
+    return Ok(expires_at);
+}
+
+/// Returns a usable id token for `context`, refreshing it when it is about to
+/// expire. `None` leaves authentication to the kubelogin exec plugin.
+pub async fn token_for_context(
+    context: &str,
+    auth_info: &AuthInfo,
+) -> Result<Option<OidcToken>, SerializableKubeError> {
+    let config = match oidc_config(auth_info) {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+
+    let current = || {
+        return stored_tokens(context).or_else(|| {
+            let id_token = config.id_token.clone()?;
+            Some(StoredTokens {
+                expires_at: id_token_expiry(&id_token).unwrap_or_else(Utc::now),
+                id_token,
+                refresh_token: config.refresh_token.clone(),
+            })
+        });
+    };
+    let usable = |stored: &StoredTokens| stored.expires_at - Duration::seconds(30) > Utc::now();
+
+    let mut stored = current();
+    if stored.as_ref().is_some_and(|stored| !usable(stored) && stored.refresh_token.is_some()) {
+        let _refreshing = REFRESH_LOCK.lock().await;
+
+        // Another request may have refreshed the tokens while we waited.
+        stored = current();
+        if let Some(StoredTokens {
+            refresh_token: Some(refresh_token),
+            ..
+        }) = stored.as_ref().filter(|stored| !usable(stored))
+        {
+            let refreshed = refresh(&config, refresh_token).await?;
+            store_tokens(context, Some(refreshed.clone()))?;
+            stored = Some(refreshed);
+        }
+    }
+
+    let stored = match stored {
+        Some(stored) if usable(&stored) => stored,
+        _ if config.source == OidcSource::Kubelogin => return Ok(None),
+        _ => {
+            return Err(SerializableKubeError::new(
+                ErrorKind::AuthExpired,
+                format!("Please log in to {} to use context {}", config.issuer_url, context),
+            ))
+        }
+    };
+
+    return Ok(Some(OidcToken {
+        id_token: stored.id_token,
+    }));
+}
+
+#[tauri::command]
+pub async fn login_oidc(
+    app_handle: tauri::AppHandle,
+    context: &str,
+    flow: Option<OidcFlow>,
+) -> Result<DateTime<Utc>, SerializableKubeError> {
+    let kubeconfig = load_kubeconfig()?.kubeconfig;
+    let auth_info = find_auth_info(&kubeconfig, context)?;
+    let config = auth_info
+        .auth_info
+        .as_ref()
+        .and_then(oidc_config)
+        .ok_or(SerializableKubeError::new(
+            ErrorKind::ExecPluginFailed,
+            format!("Context {} does not use OIDC", context),
+        ))?;
+
+    return login(&app_handle, context, &config, flow).await;
+}
+
+#[tauri::command]
+pub fn cancel_oidc_login() {
+    cancel_login();
+}
+
+#[tauri::command]
+pub fn logout_oidc(context: &str) -> Result<(), SerializableKubeError> {
+    store_tokens(context, None)?;
+    invalidate_client(context);
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::thread;
+
+    type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// A local issuer answering one request per connection with the next of
+    /// `responses`, built from its own url. Records the path and body of each
+    /// request.
+    fn serve(responses: impl FnOnce(&str) -> Vec<(u16, String)>) -> (String, Requests) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let responses = responses(&url);
+        let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header == "\r\n" {
+                        break;
+                    }
+                    if let Some(length) = header.to_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                recorded.lock().unwrap().push((
+                    request_line.split_whitespace().nth(1).unwrap().to_string(),
+                    String::from_utf8(request_body).unwrap(),
+                ));
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        )
+                        .as_bytes(),
+                    )
+                    .unwrap();
+            }
+        });
+
+        return (url, requests);
+    }
+
+    fn discovery(url: &str) -> (u16, String) {
+        return (
+            200,
+            serde_json::json!({
+                "authorization_endpoint": format!("{}/auth", url),
+                "token_endpoint": format!("{}/token", url),
+                "device_authorization_endpoint": format!("{}/device", url),
+            })
+            .to_string(),
+        );
+    }
+
+    fn id_token(expires_at: DateTime<Utc>) -> String {
+        let claims = serde_json::json!({ "exp": expires_at.timestamp() }).to_string();
+        return format!("e30.{}.c2ln", URL_SAFE_NO_PAD.encode(claims));
+    }
+
+    fn config(issuer_url: &str) -> OidcConfig {
+        return OidcConfig {
+            source: OidcSource::AuthProvider,
+            issuer_url: issuer_url.to_string(),
+            client_id: "jet-pilot".to_string(),
+            client_secret: None,
+            scopes: vec!["openid".to_string()],
+            flow: OidcFlow::AuthorizationCode,
+            loopback_port: DEFAULT_LOOPBACK_PORT,
+            id_token: None,
+            refresh_token: None,
+        };
+    }
+
+    #[test]
+    fn pkce_challenge_matches_rfc_7636() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn redirect_code_checks_the_state() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+        };
+
+        assert_eq!(redirect_code(&params(&[("code", "abc"), ("state", "xyz")]), "xyz").unwrap(), "abc");
+        assert_eq!(
+            redirect_code(&params(&[("code", "abc"), ("state", "other")]), "xyz").unwrap_err().kind,
+            ErrorKind::ExecPluginFailed
+        );
+        assert_eq!(
+            redirect_code(&params(&[("code", "abc")]), "xyz").unwrap_err().kind,
+            ErrorKind::ExecPluginFailed
+        );
+        assert_eq!(
+            redirect_code(&params(&[("error", "access_denied"), ("state", "xyz")]), "xyz")
+                .unwrap_err()
+                .kind,
+            ErrorKind::AuthExpired
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_redirect_skips_other_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let browser = tokio::spawn(async move {
+            for path in ["/favicon.ico", "/?code=abc&state=xyz"] {
+                let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+                stream
+                    .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+                    .await
+                    .unwrap();
+                let mut response = String::new();
+                let _ = tokio::io::AsyncReadExt::read_to_string(&mut stream, &mut response).await;
+            }
+        });
+
+        let params = wait_for_redirect(listener, &Notify::new()).await.unwrap();
+        browser.abort();
+
+        assert_eq!(params.get("code").map(String::as_str), Some("abc"));
+        assert_eq!(params.get("state").map(String::as_str), Some("xyz"));
+    }
+
+    #[tokio::test]
+    async fn wait_for_redirect_can_be_cancelled() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let cancel = Notify::new();
+        cancel.notify_one();
+
+        let error = wait_for_redirect(listener, &cancel).await.unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_refreshed_with_the_refresh_token() {
+        let expires_at = Utc::now() + Duration::hours(1);
+        let (url, requests) = serve(|url| {
+            vec![
+                discovery(url),
+                (
+                    200,
+                    serde_json::json!({ "id_token": id_token(expires_at) }).to_string(),
+                ),
+            ]
+        });
+
+        let mut provider = HashMap::new();
+        provider.insert("idp-issuer-url".to_string(), url.clone());
+        provider.insert("client-id".to_string(), "jet-pilot".to_string());
+        provider.insert("id-token".to_string(), id_token(Utc::now() - Duration::hours(1)));
+        provider.insert("refresh-token".to_string(), "refresh-1".to_string());
+        let auth_info: AuthInfo = serde_json::from_value(serde_json::json!({
+            "auth-provider": { "name": "oidc", "config": provider },
+        }))
+        .unwrap();
+
+        let context = format!("refresh-{}", url);
+        let token = token_for_context(&context, &auth_info).await.unwrap().unwrap();
+        assert_eq!(token.id_token, id_token(expires_at));
+
+        // The refreshed token is used until it expires, and the provider kept
+        // the refresh token because it didn't send a new one.
+        let token = token_for_context(&context, &auth_info).await.unwrap().unwrap();
+        assert_eq!(token.id_token, id_token(expires_at));
+        assert_eq!(stored_tokens(&context).unwrap().refresh_token.as_deref(), Some("refresh-1"));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "/.well-known/openid-configuration");
+        assert_eq!(requests[1].0, "/token");
+        assert!(requests[1].1.contains("grant_type=refresh_token"));
+        assert!(requests[1].1.contains("refresh_token=refresh-1"));
+    }
+
+    #[tokio::test]
+    async fn refresh_reuses_discovery_and_reports_rejected_grants() {
+        let (url, requests) = serve(|url| {
+            vec![
+                discovery(url),
+                (
+                    200,
+                    serde_json::json!({
+                        "id_token": id_token(Utc::now() + Duration::hours(1)),
+                        "refresh_token": "refresh-2",
+                    })
+                    .to_string(),
+                ),
+                (
+                    400,
+                    serde_json::json!({
+                        "error": "invalid_grant",
+                        "error_description": "Token is not active",
+                    })
+                    .to_string(),
+                ),
+            ]
+        });
+
+        let refreshed = refresh(&config(&url), "refresh-1").await.unwrap();
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("refresh-2"));
+
+        let error = refresh(&config(&url), "refresh-2").await.unwrap_err();
+        assert_eq!(error.kind, ErrorKind::AuthExpired);
+
+        let paths: Vec<String> = requests.lock().unwrap().iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, ["/.well-known/openid-configuration", "/token", "/token"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn device_code_polling_waits_and_slows_down() {
+        let (url, requests) = serve(|_| {
+            vec![
+                (400, serde_json::json!({ "error": "authorization_pending" }).to_string()),
+                (400, serde_json::json!({ "error": "slow_down" }).to_string()),
+                (400, serde_json::json!({ "error": "authorization_pending" }).to_string()),
+                (
+                    200,
+                    serde_json::json!({
+                        "id_token": id_token(Utc::now() + Duration::hours(1)),
+                        "refresh_token": "refresh-1",
+                    })
+                    .to_string(),
+                ),
+            ]
+        });
+
+        let started = tokio::time::Instant::now();
+        let stored = poll_device_token(
+            &config(&url),
+            &format!("{}/token", url),
+            "device-1",
+            std::time::Duration::from_secs(1),
+            std::time::Duration::from_secs(600),
+            &Notify::new(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(stored.refresh_token.as_deref(), Some("refresh-1"));
+        // 1s, 1s, then twice 1s + 5s after slowing down.
+        assert!(started.elapsed() >= std::time::Duration::from_secs(14));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[0].1.contains("device_code=device-1"));
+        assert!(requests[0].1.contains("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn device_code_polling_gives_up_when_the_code_expires() {
+        let (url, _) = serve(|_| {
+            vec![(400, serde_json::json!({ "error": "authorization_pending" }).to_string()); 2]
+        });
+
+        let error = poll_device_token(
+            &config(&url),
+            &format!("{}/token", url),
+            "device-1",
+            std::time::Duration::from_secs(5),
+            std::time::Duration::from_secs(10),
+            &Notify::new(),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Timeout);
+    }
+}
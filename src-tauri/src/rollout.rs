@@ -0,0 +1,895 @@
+use crate::dynamic::{diff_values, DiffEntry};
+use crate::watch::register_watch;
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use futures::StreamExt;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::core::v1::PodTemplateSpec;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, Time};
+use kube::api::{Api, ListParams, Patch, PatchParams};
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::ResourceExt;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+const CHANGE_CAUSE_ANNOTATION: &str = "kubernetes.io/change-cause";
+const DEFAULT_ROLLOUT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutRevision {
+    pub revision: i64,
+    pub replica_set: String,
+    pub change_cause: Option<String>,
+    pub created_at: Option<Time>,
+    pub replicas: i32,
+    pub current: bool,
+    pub template: Option<PodTemplateSpec>,
+    /// Changes to the pod template compared to the previous revision.
+    pub changes: Vec<DiffEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum RolloutPhase {
+    Progressing,
+    Complete,
+    Failed,
+    TimedOut,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RolloutCondition {
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutStatus {
+    pub phase: RolloutPhase,
+    pub message: String,
+    pub replicas: i32,
+    pub updated_replicas: i32,
+    pub ready_replicas: i32,
+    pub available_replicas: i32,
+    pub progressing: Option<RolloutCondition>,
+    /// Pods with an ordinal below the partition of a StatefulSet keep their
+    /// old revision.
+    pub partition: Option<i32>,
+}
+
+/// Payload of the `rollout_status_<subscription_id>` events.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", content = "object", rename_all = "UPPERCASE")]
+pub enum RolloutEvent {
+    Status(RolloutStatus),
+    Error(SerializableKubeError),
+}
+
+/// Renders `selector` the way `labelSelector` query parameters expect it,
+/// including set-based requirements.
+pub fn label_selector_string(selector: &LabelSelector) -> String {
+    let labels = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .map(|(key, value)| format!("{}={}", key, value));
+
+    let expressions = selector
+        .match_expressions
+        .iter()
+        .flatten()
+        .map(|expression| {
+            let values = expression.values.clone().unwrap_or_default().join(",");
+            match expression.operator.as_str() {
+                "In" => format!("{} in ({})", expression.key, values),
+                "NotIn" => format!("{} notin ({})", expression.key, values),
+                "DoesNotExist" => format!("!{}", expression.key),
+                _ => expression.key.clone(),
+            }
+        });
+
+    return labels.chain(expressions).collect::<Vec<String>>().join(",");
+}
+
+fn revision_of<K: ResourceExt>(object: &K) -> Option<i64> {
+    return object.annotations().get(REVISION_ANNOTATION)?.parse().ok();
+}
+
+/// The pod template without the `pod-template-hash` label the controller adds
+/// to each ReplicaSet, so templates of different revisions can be compared.
+fn normalized_template(replica_set: &ReplicaSet) -> Option<PodTemplateSpec> {
+    let mut template = replica_set.spec.as_ref()?.template.clone()?;
+    if let Some(labels) = template.metadata.as_mut().and_then(|metadata| metadata.labels.as_mut()) {
+        labels.remove("pod-template-hash");
+    }
+
+    return Some(template);
+}
+
+async fn get_deployment(api: &Api<Deployment>, name: &str) -> Result<Deployment, SerializableKubeError> {
+    return api.get(name).await.map_err(|err| SerializableKubeError::from(err));
+}
+
+/// Returns the ReplicaSets owned by `deployment`, oldest revision first.
+async fn owned_replica_sets(
+    api: &Api<ReplicaSet>,
+    deployment: &Deployment,
+) -> Result<Vec<ReplicaSet>, SerializableKubeError> {
+    let selector = deployment
+        .spec
+        .as_ref()
+        .map(|spec| label_selector_string(&spec.selector))
+        .unwrap_or_default();
+
+    let mut replica_sets: Vec<ReplicaSet> = api
+        .list(&ListParams::default().labels(&selector))
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?
+        .items
+        .into_iter()
+        .filter(|replica_set| {
+            replica_set
+                .owner_references()
+                .iter()
+                .any(|owner| Some(&owner.uid) == deployment.metadata.uid.as_ref())
+        })
+        .filter(|replica_set| revision_of(replica_set).is_some())
+        .collect();
+
+    replica_sets.sort_by_key(|replica_set| revision_of(replica_set));
+    return Ok(replica_sets);
+}
+
+#[tauri::command]
+pub async fn get_rollout_history(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<Vec<RolloutRevision>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let deployment_api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let replica_set_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    let deployment = get_deployment(&deployment_api, name).await?;
+    let current_revision = revision_of(&deployment);
+
+    let mut history: Vec<RolloutRevision> = Vec::new();
+    let mut previous: Option<serde_json::Value> = None;
+
+    for replica_set in owned_replica_sets(&replica_set_api, &deployment).await? {
+        let template = normalized_template(&replica_set);
+        let template_value = serde_json::to_value(&template).ok();
+
+        let mut changes: Vec<DiffEntry> = Vec::new();
+        if previous.is_some() {
+            diff_values("", previous.as_ref(), template_value.as_ref(), &mut changes);
+        }
+
+        history.push(RolloutRevision {
+            revision: revision_of(&replica_set).unwrap_or_default(),
+            replica_set: replica_set.name_any(),
+            change_cause: replica_set.annotations().get(CHANGE_CAUSE_ANNOTATION).cloned(),
+            created_at: replica_set.metadata.creation_timestamp.clone(),
+            replicas: replica_set.status.as_ref().map(|status| status.replicas).unwrap_or_default(),
+            current: revision_of(&replica_set) == current_revision,
+            template,
+            changes,
+        });
+
+        previous = template_value;
+    }
+
+    return Ok(history);
+}
+
+/// Rolls back to `revision`, or to the revision before the current one, by
+/// copying that ReplicaSet's pod template into the Deployment. The patch only
+/// applies to the Deployment that was read, so a change in between fails it
+/// instead of being overwritten.
+#[tauri::command]
+pub async fn undo_rollout(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    revision: Option<i64>,
+) -> Result<Deployment, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let deployment_api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let replica_set_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    let deployment = get_deployment(&deployment_api, name).await?;
+    if deployment.spec.as_ref().and_then(|spec| spec.paused) == Some(true) {
+        return Err(SerializableKubeError::new(
+            ErrorKind::Conflict,
+            format!("Deployment {} is paused, resume it before rolling back", name),
+        ));
+    }
+
+    let current_revision = revision_of(&deployment).unwrap_or_default();
+    let replica_sets = owned_replica_sets(&replica_set_api, &deployment).await?;
+    let target = match revision {
+        Some(revision) => replica_sets
+            .iter()
+            .find(|replica_set| revision_of(*replica_set) == Some(revision)),
+        None => replica_sets
+            .iter()
+            .rev()
+            .find(|replica_set| revision_of(*replica_set).unwrap_or_default() < current_revision),
+    };
+
+    let template = target.and_then(normalized_template).ok_or(SerializableKubeError::new(
+        ErrorKind::NotFound,
+        match revision {
+            Some(revision) => format!("Revision {} of deployment {} not found", revision, name),
+            None => format!("Deployment {} has no previous revision", name),
+        },
+    ))?;
+
+    let patch: json_patch::Patch = serde_json::from_value(serde_json::json!([
+        { "op": "test", "path": "/metadata/resourceVersion", "value": deployment.resource_version() },
+        { "op": "replace", "path": "/spec/template", "value": template }
+    ]))
+    .map_err(|err| SerializableKubeError::new(ErrorKind::Unknown, err.to_string()))?;
+
+    return deployment_api
+        .patch(name, &PatchParams::default(), &Patch::Json::<()>(patch))
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+async fn set_paused(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    paused: bool,
+) -> Result<Deployment, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let deployment_api: Api<Deployment> = Api::namespaced(client, namespace);
+
+    return deployment_api
+        .patch(
+            name,
+            &PatchParams::default(),
+            &Patch::Merge(serde_json::json!({ "spec": { "paused": paused } })),
+        )
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn pause_rollout(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<Deployment, SerializableKubeError> {
+    return set_paused(context, namespace, name, true).await;
+}
+
+#[tauri::command]
+pub async fn resume_rollout(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<Deployment, SerializableKubeError> {
+    return set_paused(context, namespace, name, false).await;
+}
+
+/// Works out how far a rollout got, following the same rules as
+/// `kubectl rollout status`. A paused rollout that is not done yet stays
+/// progressing, with a message saying it needs to be resumed.
+pub fn deployment_rollout_status(deployment: &Deployment) -> RolloutStatus {
+    let desired = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.replicas)
+        .unwrap_or(1);
+    let status = deployment.status.clone().unwrap_or_default();
+    let updated = status.updated_replicas.unwrap_or_default();
+    let available = status.available_replicas.unwrap_or_default();
+
+    let progressing = status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|condition| condition.type_ == "Progressing")
+        .map(|condition| RolloutCondition {
+            status: condition.status.clone(),
+            reason: condition.reason.clone(),
+            message: condition.message.clone(),
+        });
+
+    let observed = status.observed_generation.unwrap_or_default()
+        >= deployment.metadata.generation.unwrap_or_default();
+
+    let paused = deployment.spec.as_ref().and_then(|spec| spec.paused) == Some(true);
+
+    let (phase, message) = if !observed {
+        (RolloutPhase::Progressing, "Waiting for the deployment spec update to be observed".to_string())
+    } else if progressing.as_ref().and_then(|condition| condition.reason.as_deref())
+        == Some("ProgressDeadlineExceeded")
+    {
+        (RolloutPhase::Failed, format!("Deployment {} exceeded its progress deadline", deployment.name_any()))
+    } else if updated < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} updated replicas are available", updated, desired),
+        )
+    } else if status.replicas.unwrap_or_default() > updated {
+        (
+            RolloutPhase::Progressing,
+            format!(
+                "{} old replicas are pending termination",
+                status.replicas.unwrap_or_default() - updated
+            ),
+        )
+    } else if available < updated {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} updated replicas are available", available, updated),
+        )
+    } else {
+        (RolloutPhase::Complete, format!("Deployment {} successfully rolled out", deployment.name_any()))
+    };
+    let message = if paused && phase == RolloutPhase::Progressing {
+        format!("Deployment {} is paused: {}", deployment.name_any(), message)
+    } else {
+        message
+    };
+
+    return RolloutStatus {
+        phase,
+        message,
+        replicas: status.replicas.unwrap_or_default(),
+        updated_replicas: updated,
+        ready_replicas: status.ready_replicas.unwrap_or_default(),
+        available_replicas: available,
+        progressing,
+        partition: None,
+    };
+}
+
+fn waiting_for_generation(metadata_generation: Option<i64>, observed_generation: Option<i64>) -> bool {
+    return observed_generation.unwrap_or_default() < metadata_generation.unwrap_or_default();
+}
+
+/// Like `kubectl rollout status`, but also reports a partitioned update as
+/// complete once every pod at or above the partition runs the new revision.
+pub fn statefulset_rollout_status(stateful_set: &StatefulSet) -> RolloutStatus {
+    let spec = stateful_set.spec.clone().unwrap_or_default();
+    let status = stateful_set.status.clone().unwrap_or_default();
+    let desired = spec.replicas.unwrap_or(1);
+    let rolling_update = spec
+        .update_strategy
+        .as_ref()
+        .is_none_or(|strategy| strategy.type_.as_deref().unwrap_or("RollingUpdate") == "RollingUpdate");
+    let partition = spec
+        .update_strategy
+        .as_ref()
+        .and_then(|strategy| strategy.rolling_update.as_ref())
+        .and_then(|rolling_update| rolling_update.partition)
+        .filter(|partition| *partition > 0);
+
+    let updated = status.updated_replicas.unwrap_or_default();
+    let ready = status.ready_replicas.unwrap_or_default();
+
+    let (phase, message) = if !rolling_update {
+        (
+            RolloutPhase::Failed,
+            "Rollout status is only available for the RollingUpdate strategy".to_string(),
+        )
+    } else if waiting_for_generation(stateful_set.metadata.generation, status.observed_generation) {
+        (RolloutPhase::Progressing, "Waiting for the statefulset spec update to be observed".to_string())
+    } else if ready < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} pods are ready", ready, desired),
+        )
+    } else if let Some(partition) = partition {
+        if updated < desired - partition {
+            (
+                RolloutPhase::Progressing,
+                format!(
+                    "Waiting for the partitioned rollout to finish: {} of {} new pods have been updated",
+                    updated,
+                    desired - partition
+                ),
+            )
+        } else {
+            (
+                RolloutPhase::Complete,
+                format!("Partitioned rollout complete: {} new pods have been updated", updated),
+            )
+        }
+    } else if status.update_revision.is_some() && status.update_revision != status.current_revision {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} pods are at the new revision", updated, desired),
+        )
+    } else {
+        (
+            RolloutPhase::Complete,
+            format!("StatefulSet {} successfully rolled out", stateful_set.name_any()),
+        )
+    };
+
+    return RolloutStatus {
+        phase,
+        message,
+        replicas: status.replicas,
+        updated_replicas: updated,
+        ready_replicas: ready,
+        available_replicas: status.available_replicas.unwrap_or_default(),
+        progressing: None,
+        partition,
+    };
+}
+
+pub fn daemonset_rollout_status(daemon_set: &DaemonSet) -> RolloutStatus {
+    let status = daemon_set.status.clone().unwrap_or_default();
+    let rolling_update = daemon_set
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.update_strategy.as_ref())
+        .is_none_or(|strategy| strategy.type_.as_deref().unwrap_or("RollingUpdate") == "RollingUpdate");
+
+    let desired = status.desired_number_scheduled;
+    let updated = status.updated_number_scheduled.unwrap_or_default();
+    let available = status.number_available.unwrap_or_default();
+
+    let (phase, message) = if !rolling_update {
+        (
+            RolloutPhase::Failed,
+            "Rollout status is only available for the RollingUpdate strategy".to_string(),
+        )
+    } else if waiting_for_generation(daemon_set.metadata.generation, status.observed_generation) {
+        (RolloutPhase::Progressing, "Waiting for the daemonset spec update to be observed".to_string())
+    } else if updated < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} updated pods have been scheduled", updated, desired),
+        )
+    } else if available < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} updated pods are available", available, desired),
+        )
+    } else {
+        (
+            RolloutPhase::Complete,
+            format!("DaemonSet {} successfully rolled out", daemon_set.name_any()),
+        )
+    };
+
+    return RolloutStatus {
+        phase,
+        message,
+        replicas: desired,
+        updated_replicas: updated,
+        ready_replicas: status.number_ready,
+        available_replicas: available,
+        progressing: None,
+        partition: None,
+    };
+}
+
+/// ReplicaSets don't roll out on their own, so they are done once every
+/// desired replica is available, and failed when the controller reports that
+/// it cannot create them, for example because of a quota.
+pub fn replicaset_rollout_status(replica_set: &ReplicaSet) -> RolloutStatus {
+    let status = replica_set.status.clone().unwrap_or_default();
+    let desired = replica_set
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.replicas)
+        .unwrap_or(1);
+    let ready = status.ready_replicas.unwrap_or_default();
+    let available = status.available_replicas.unwrap_or_default();
+
+    let replica_failure = status
+        .conditions
+        .iter()
+        .flatten()
+        .find(|condition| condition.type_ == "ReplicaFailure" && condition.status == "True");
+
+    let (phase, message) = if waiting_for_generation(replica_set.metadata.generation, status.observed_generation) {
+        (RolloutPhase::Progressing, "Waiting for the replicaset spec update to be observed".to_string())
+    } else if let Some(condition) = replica_failure.filter(|_| ready < desired) {
+        (
+            RolloutPhase::Failed,
+            condition
+                .message
+                .clone()
+                .unwrap_or(format!("ReplicaSet {} cannot create its pods", replica_set.name_any())),
+        )
+    } else if ready < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} pods are ready", ready, desired),
+        )
+    } else if available < desired {
+        (
+            RolloutPhase::Progressing,
+            format!("{} of {} pods are available", available, desired),
+        )
+    } else {
+        (
+            RolloutPhase::Complete,
+            format!("ReplicaSet {} has all {} replicas available", replica_set.name_any(), desired),
+        )
+    };
+
+    return RolloutStatus {
+        phase,
+        message,
+        replicas: status.replicas,
+        updated_replicas: status.fully_labeled_replicas.unwrap_or_default(),
+        ready_replicas: ready,
+        available_replicas: available,
+        progressing: None,
+        partition: None,
+    };
+}
+
+/// Emits `rollout_status_<subscription_id>` events for `api`'s object `name`
+/// until `status_of` reports the rollout as complete or failed, or the timeout
+/// expires. The task can be stopped early with `unwatch`.
+pub fn spawn_rollout_status<K>(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    api: Api<K>,
+    name: &str,
+    timeout_seconds: Option<u64>,
+    status_of: fn(&K) -> RolloutStatus,
+) where
+    K: kube::Resource + Clone + serde::de::DeserializeOwned + std::fmt::Debug + Send + Sync + 'static,
+{
+    let event_name = format!("rollout_status_{}", subscription_id);
+    let config = watcher::Config::default().fields(&format!("metadata.name={}", name));
+    let timeout = timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_ROLLOUT_TIMEOUT);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut last_status: Option<RolloutStatus> = None;
+
+        let follow = async {
+            let mut stream = watcher(api, config).default_backoff().applied_objects().boxed();
+
+            while let Some(object) = stream.next().await {
+                let event = match object {
+                    Ok(object) => {
+                        let status = status_of(&object);
+                        last_status = Some(status.clone());
+                        RolloutEvent::Status(status)
+                    }
+                    Err(err) => RolloutEvent::Error(SerializableKubeError::from(err)),
+                };
+
+                let finished = matches!(&event, RolloutEvent::Status(status) if status.phase != RolloutPhase::Progressing);
+                if app_handle.emit_all(&event_name, event).is_err() || finished {
+                    return;
+                }
+            }
+        };
+
+        if tokio::time::timeout(timeout, follow).await.is_err() {
+            if let Some(mut status) = last_status {
+                status.phase = RolloutPhase::TimedOut;
+                status.message = format!("Timed out after {}s: {}", timeout.as_secs(), status.message);
+                let _ = app_handle.emit_all(&event_name, RolloutEvent::Status(status));
+            }
+        }
+    });
+
+    register_watch(subscription_id, handle);
+}
+
+#[tauri::command]
+pub async fn watch_rollout_status(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    name: &str,
+    timeout_seconds: Option<u64>,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let deployment_api: Api<Deployment> = Api::namespaced(client, namespace);
+
+    spawn_rollout_status(
+        app_handle,
+        subscription_id,
+        deployment_api,
+        name,
+        timeout_seconds,
+        deployment_rollout_status,
+    );
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_selector_string_includes_expressions() {
+        let selector: LabelSelector = serde_json::from_value(serde_json::json!({
+            "matchLabels": { "app": "web", "tier": "frontend" },
+            "matchExpressions": [
+                { "key": "env", "operator": "In", "values": ["prod", "staging"] },
+                { "key": "track", "operator": "NotIn", "values": ["canary"] },
+                { "key": "release", "operator": "Exists" },
+                { "key": "legacy", "operator": "DoesNotExist" },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            label_selector_string(&selector),
+            "app=web,tier=frontend,env in (prod,staging),track notin (canary),release,!legacy"
+        );
+        assert_eq!(label_selector_string(&LabelSelector::default()), "");
+    }
+
+    fn assert_statuses<K: serde::de::DeserializeOwned>(
+        status_of: fn(&K) -> RolloutStatus,
+        cases: Vec<(&str, serde_json::Value, RolloutPhase, &str)>,
+    ) {
+        for (case, object, phase, message) in cases {
+            let object: K = serde_json::from_value(object).unwrap();
+            let status = status_of(&object);
+
+            assert_eq!(status.phase, phase, "{}", case);
+            assert!(
+                status.message.contains(message),
+                "{}: {:?} does not contain {:?}",
+                case,
+                status.message,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn deployment_rollout_phases() {
+        let deployment = |paused: bool, status: serde_json::Value| {
+            serde_json::json!({
+                "metadata": { "name": "web", "generation": 2 },
+                "spec": {
+                    "replicas": 3,
+                    "paused": paused,
+                    "selector": {},
+                    "template": {},
+                },
+                "status": status,
+            })
+        };
+
+        assert_statuses(
+            deployment_rollout_status,
+            vec![
+                (
+                    "progressing",
+                    deployment(false, serde_json::json!({
+                        "observedGeneration": 2, "replicas": 4, "updatedReplicas": 2, "availableReplicas": 3,
+                    })),
+                    RolloutPhase::Progressing,
+                    "2 of 3 updated replicas are available",
+                ),
+                (
+                    "complete",
+                    deployment(false, serde_json::json!({
+                        "observedGeneration": 2, "replicas": 3, "updatedReplicas": 3, "availableReplicas": 3,
+                    })),
+                    RolloutPhase::Complete,
+                    "successfully rolled out",
+                ),
+                (
+                    "stalled",
+                    deployment(false, serde_json::json!({
+                        "observedGeneration": 2, "replicas": 4, "updatedReplicas": 1, "availableReplicas": 3,
+                        "conditions": [{
+                            "type": "Progressing", "status": "False", "reason": "ProgressDeadlineExceeded",
+                        }],
+                    })),
+                    RolloutPhase::Failed,
+                    "exceeded its progress deadline",
+                ),
+                (
+                    "paused",
+                    deployment(true, serde_json::json!({
+                        "observedGeneration": 2, "replicas": 4, "updatedReplicas": 1, "availableReplicas": 3,
+                        "conditions": [{ "type": "Progressing", "status": "Unknown", "reason": "DeploymentPaused" }],
+                    })),
+                    RolloutPhase::Progressing,
+                    "is paused",
+                ),
+                (
+                    "spec not observed",
+                    deployment(false, serde_json::json!({ "observedGeneration": 1 })),
+                    RolloutPhase::Progressing,
+                    "to be observed",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn statefulset_rollout_phases() {
+        let statefulset = |strategy: serde_json::Value, status: serde_json::Value| {
+            serde_json::json!({
+                "metadata": { "name": "db", "generation": 2 },
+                "spec": {
+                    "replicas": 3,
+                    "serviceName": "db",
+                    "selector": {},
+                    "template": {},
+                    "updateStrategy": strategy,
+                },
+                "status": status,
+            })
+        };
+        let rolling_update = serde_json::json!({ "type": "RollingUpdate" });
+
+        assert_statuses(
+            statefulset_rollout_status,
+            vec![
+                (
+                    "progressing",
+                    statefulset(rolling_update.clone(), serde_json::json!({
+                        "observedGeneration": 2, "replicas": 3, "readyReplicas": 3, "updatedReplicas": 1,
+                        "currentRevision": "db-1", "updateRevision": "db-2",
+                    })),
+                    RolloutPhase::Progressing,
+                    "1 of 3 pods are at the new revision",
+                ),
+                (
+                    "complete",
+                    statefulset(rolling_update.clone(), serde_json::json!({
+                        "observedGeneration": 2, "replicas": 3, "readyReplicas": 3, "updatedReplicas": 3,
+                        "currentRevision": "db-2", "updateRevision": "db-2",
+                    })),
+                    RolloutPhase::Complete,
+                    "successfully rolled out",
+                ),
+                (
+                    "stalled",
+                    statefulset(serde_json::json!({ "type": "OnDelete" }), serde_json::json!({
+                        "observedGeneration": 2, "replicas": 3, "readyReplicas": 3,
+                    })),
+                    RolloutPhase::Failed,
+                    "only available for the RollingUpdate strategy",
+                ),
+                (
+                    "paused",
+                    statefulset(
+                        serde_json::json!({ "type": "RollingUpdate", "rollingUpdate": { "partition": 3 } }),
+                        serde_json::json!({
+                            "observedGeneration": 2, "replicas": 3, "readyReplicas": 3, "updatedReplicas": 0,
+                            "currentRevision": "db-1", "updateRevision": "db-2",
+                        }),
+                    ),
+                    RolloutPhase::Complete,
+                    "Partitioned rollout complete: 0 new pods",
+                ),
+                (
+                    "partition not reached",
+                    statefulset(
+                        serde_json::json!({ "type": "RollingUpdate", "rollingUpdate": { "partition": 1 } }),
+                        serde_json::json!({
+                            "observedGeneration": 2, "replicas": 3, "readyReplicas": 3, "updatedReplicas": 1,
+                            "currentRevision": "db-1", "updateRevision": "db-2",
+                        }),
+                    ),
+                    RolloutPhase::Progressing,
+                    "1 of 2 new pods have been updated",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn daemonset_rollout_phases() {
+        let daemonset = |strategy: &str, status: serde_json::Value| {
+            serde_json::json!({
+                "metadata": { "name": "agent", "generation": 2 },
+                "spec": { "selector": {}, "template": {}, "updateStrategy": { "type": strategy } },
+                "status": status,
+            })
+        };
+
+        assert_statuses(
+            daemonset_rollout_status,
+            vec![
+                (
+                    "progressing",
+                    daemonset("RollingUpdate", serde_json::json!({
+                        "observedGeneration": 2, "desiredNumberScheduled": 4, "currentNumberScheduled": 4,
+                        "numberMisscheduled": 0, "numberReady": 4, "updatedNumberScheduled": 2, "numberAvailable": 4,
+                    })),
+                    RolloutPhase::Progressing,
+                    "2 of 4 updated pods have been scheduled",
+                ),
+                (
+                    "complete",
+                    daemonset("RollingUpdate", serde_json::json!({
+                        "observedGeneration": 2, "desiredNumberScheduled": 4, "currentNumberScheduled": 4,
+                        "numberMisscheduled": 0, "numberReady": 4, "updatedNumberScheduled": 4, "numberAvailable": 4,
+                    })),
+                    RolloutPhase::Complete,
+                    "successfully rolled out",
+                ),
+                (
+                    "stalled",
+                    daemonset("OnDelete", serde_json::json!({
+                        "observedGeneration": 2, "desiredNumberScheduled": 4, "currentNumberScheduled": 4,
+                        "numberMisscheduled": 0, "numberReady": 4, "updatedNumberScheduled": 0,
+                    })),
+                    RolloutPhase::Failed,
+                    "only available for the RollingUpdate strategy",
+                ),
+                (
+                    "unavailable",
+                    daemonset("RollingUpdate", serde_json::json!({
+                        "observedGeneration": 2, "desiredNumberScheduled": 4, "currentNumberScheduled": 4,
+                        "numberMisscheduled": 0, "numberReady": 3, "updatedNumberScheduled": 4, "numberAvailable": 3,
+                    })),
+                    RolloutPhase::Progressing,
+                    "3 of 4 updated pods are available",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn replicaset_rollout_phases() {
+        let replicaset = |status: serde_json::Value| {
+            serde_json::json!({
+                "metadata": { "name": "web-5d8f", "generation": 1 },
+                "spec": { "replicas": 3, "selector": {} },
+                "status": status,
+            })
+        };
+
+        assert_statuses(
+            replicaset_rollout_status,
+            vec![
+                (
+                    "progressing",
+                    replicaset(serde_json::json!({ "observedGeneration": 1, "replicas": 3, "readyReplicas": 1 })),
+                    RolloutPhase::Progressing,
+                    "1 of 3 pods are ready",
+                ),
+                (
+                    "complete",
+                    replicaset(serde_json::json!({
+                        "observedGeneration": 1, "replicas": 3, "readyReplicas": 3, "availableReplicas": 3,
+                    })),
+                    RolloutPhase::Complete,
+                    "has all 3 replicas available",
+                ),
+                (
+                    "stalled",
+                    replicaset(serde_json::json!({
+                        "observedGeneration": 1, "replicas": 1, "readyReplicas": 1,
+                        "conditions": [{
+                            "type": "ReplicaFailure", "status": "True", "reason": "FailedCreate",
+                            "message": "pods \"web-5d8f-x\" is forbidden: exceeded quota",
+                        }],
+                    })),
+                    RolloutPhase::Failed,
+                    "exceeded quota",
+                ),
+                (
+                    "spec not observed",
+                    replicaset(serde_json::json!({ "observedGeneration": 0, "replicas": 3 })),
+                    RolloutPhase::Progressing,
+                    "to be observed",
+                ),
+            ],
+        );
+    }
+}
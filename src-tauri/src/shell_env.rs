@@ -0,0 +1,55 @@
+use std::env;
+use std::process::{Command, Stdio};
+
+const DELIMITER: &str = "_SHELL_ENV_DELIMITER_";
+
+/// Picks `PATH` out of the output of `env`, which the shell prints between
+/// two delimiters so whatever its startup files print is ignored.
+fn path_from_env_output(output: &str) -> Option<String> {
+    let env_output = output.split(DELIMITER).nth(1)?;
+
+    return env_output
+        .lines()
+        .find_map(|line| line.strip_prefix("PATH="))
+        .map(|path| path.to_string());
+}
+
+/// Apps started from the Finder or a desktop launcher don't get the `PATH`
+/// the user's login shell sets up, so exec plugins like `aws` or `kubelogin`
+/// would not be found. This takes `PATH` from the login shell instead.
+/// Windows apps already get the user's `PATH`.
+pub fn fix_path() {
+    if cfg!(target_os = "windows") {
+        return;
+    }
+
+    let shell = env::var("SHELL").unwrap_or("/bin/sh".to_string());
+    let output = Command::new(shell)
+        .arg("-ilc")
+        .arg(format!("printf {0}; env; printf {0}; exit", DELIMITER))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+
+    if let Ok(output) = output {
+        if let Some(path) = path_from_env_output(&String::from_utf8_lossy(&output.stdout)) {
+            env::set_var("PATH", path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_is_read_between_the_delimiters() {
+        let output = format!(
+            "Welcome back!\nPATH=/from/motd\n{0}HOME=/home/dev\nPATH=/opt/homebrew/bin:/usr/bin\nSHELL=/bin/zsh\n{0}",
+            DELIMITER
+        );
+
+        assert_eq!(path_from_env_output(&output), Some("/opt/homebrew/bin:/usr/bin".to_string()));
+        assert_eq!(path_from_env_output("no delimiters"), None);
+    }
+}
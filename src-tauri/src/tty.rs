@@ -0,0 +1,491 @@
+use crate::{client_with_context, SerializableKubeError};
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::{SinkExt, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+use kube::api::{Api, AttachParams, TerminalSize};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{io::{Read, Write}, sync::{Arc, Mutex}, thread};
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// A terminal is either a local PTY running a command, or a native exec
+/// session in a container. Both take input through `write_to_pty` and emit
+/// their output on `tty_data_<session_id>`.
+enum TerminalSession {
+    Pty {
+        writer: Arc<Mutex<Box<dyn Write + Send>>>,
+        master: Box<dyn MasterPty + Send>,
+        killer: Box<dyn ChildKiller + Send + Sync>,
+    },
+    Exec {
+        stdin: UnboundedSender<Vec<u8>>,
+        /// The latest `(rows, cols)`, forwarded to the container by a task.
+        resize: watch::Sender<(u16, u16)>,
+        /// Set by the first input or resize, which the view only sends once
+        /// it listens for the output of the session.
+        attached: watch::Sender<bool>,
+        tasks: Vec<JoinHandle<()>>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtySessionKind {
+    Pty,
+    Exec,
+}
+
+/// What a session runs, so the UI can reattach to its sessions after a
+/// reload through `list_tty_sessions`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtySessionInfo {
+    pub session_id: String,
+    pub kind: TtySessionKind,
+    pub command: Vec<String>,
+    pub context: Option<String>,
+    pub namespace: Option<String>,
+    pub pod: Option<String>,
+    pub container: Option<String>,
+}
+
+struct TtySession {
+    info: TtySessionInfo,
+    terminal: TerminalSession,
+}
+
+const DEFAULT_ROWS: u16 = 24;
+const DEFAULT_COLS: u16 = 80;
+/// How long the exit of a PTY child waits for the rest of its output. A
+/// background process that inherited the terminal keeps it open, so the
+/// reader may never finish.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long `stop_tty_session` waits for the shell to exit before it kills
+/// the session.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Payload of the `tty_exit_<session_id>` event, sent when the remote
+/// process ended. `exit_code` is missing when the exec itself failed.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtyExit {
+    pub exit_code: Option<i32>,
+    pub message: Option<String>,
+}
+
+static TTY_SESSIONS: Mutex<Option<HashMap<String, TtySession>>> = Mutex::new(None);
+
+/// Drops a session, which closes its writer and the PTY master or exec
+/// connection.
+fn remove_session(session_id: &str) -> Option<TtySession> {
+    return TTY_SESSIONS
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|sessions| sessions.remove(session_id));
+}
+
+fn emit_exit(app_handle: &tauri::AppHandle, session_id: &str, exit: TtyExit) {
+    let _ = app_handle.emit_all(format!("tty_exit_{}", session_id).as_ref(), exit);
+}
+
+#[tauri::command]
+pub fn create_tty_session(
+    app_handle: tauri::AppHandle,
+    init_command: Vec<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> String {
+    let pty_system = native_pty_system();
+    let pty_pair = pty_system
+        .openpty(PtySize {
+            rows: rows.unwrap_or(DEFAULT_ROWS),
+            cols: cols.unwrap_or(DEFAULT_COLS),
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .unwrap();
+
+    // generate a random session id
+    let session_id = Uuid::new_v4().to_string();
+    let info = TtySessionInfo {
+        session_id: session_id.clone(),
+        kind: TtySessionKind::Pty,
+        command: init_command.clone(),
+        context: None,
+        namespace: None,
+        pod: None,
+        container: None,
+    };
+
+    #[cfg(target_os = "windows")]
+    let cmd = CommandBuilder::new("powershell.exe");
+    #[cfg(not(target_os = "windows"))]
+    let cmd = CommandBuilder::from_argv(
+        init_command
+            .into_iter()
+            .map(|s| OsString::from(s))
+            .collect(),
+    );
+
+    let mut child = pty_pair.slave.spawn_command(cmd).unwrap();
+    let killer = child.clone_killer();
+    let mut reader = pty_pair.master.try_clone_reader().unwrap();
+    let writer = pty_pair.master.take_writer().unwrap();
+
+    // The session is registered before the threads start, so a child that
+    // exits right away is still cleaned up.
+    let mut sessions = TTY_SESSIONS.lock().unwrap();
+
+    let reader_app_handle = app_handle.clone();
+    let reader_session_id = session_id.clone();
+    let (reader_done, reader_finished) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    let data = buffer[..read].to_vec();
+                    let event = format!("tty_data_{}", reader_session_id);
+                    if reader_app_handle.emit_all(event.as_ref(), data).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = reader_done.send(());
+    });
+
+    let wait_session_id = session_id.clone();
+    thread::spawn(move || {
+        let status = child.wait();
+
+        // Dropping the master ends the reader on every platform, once the
+        // remaining output has been read, unless another process still holds
+        // the terminal.
+        remove_session(&wait_session_id);
+        let _ = reader_finished.recv_timeout(OUTPUT_DRAIN_TIMEOUT);
+
+        let exit = match status {
+            Ok(status) => TtyExit {
+                exit_code: Some(status.exit_code() as i32),
+                message: None,
+            },
+            Err(err) => TtyExit {
+                exit_code: None,
+                message: Some(err.to_string()),
+            },
+        };
+        emit_exit(&app_handle, &wait_session_id, exit);
+    });
+
+    sessions.get_or_insert_with(HashMap::new).insert(
+        session_id.clone(),
+        TtySession {
+            info,
+            terminal: TerminalSession::Pty {
+                writer: Arc::new(Mutex::new(writer)),
+                master: pty_pair.master,
+                killer,
+            },
+        },
+    );
+
+    return session_id;
+}
+
+/// Reads the exit code from the status the api server sends when an exec
+/// ends: `Success`, or a `NonZeroExitCode` failure with the code as a cause.
+fn exit_of(status: Option<Status>) -> TtyExit {
+    let status = match status {
+        Some(status) => status,
+        None => {
+            return TtyExit {
+                exit_code: None,
+                message: Some("Connection to the container was lost".to_string()),
+            }
+        }
+    };
+
+    let exit_code = status
+        .details
+        .as_ref()
+        .and_then(|details| details.causes.as_ref())
+        .and_then(|causes| causes.iter().find(|cause| cause.reason.as_deref() == Some("ExitCode")))
+        .and_then(|cause| cause.message.as_ref())
+        .and_then(|message| message.parse().ok())
+        .or(if status.status.as_deref() == Some("Success") { Some(0) } else { None });
+
+    return TtyExit {
+        exit_code,
+        message: status.message,
+    };
+}
+
+/// Starts `command` in a container through the exec api, without kubectl or
+/// a local PTY, and returns a session id that works with `write_to_pty` and
+/// `tty_data_<session_id>` like `create_tty_session`. The exit code of the
+/// command is emitted on `tty_exit_<session_id>`.
+///
+/// The view can only listen once it knows the session id, so output and the
+/// exit are held back until its first `write_to_pty` or `resize_tty`.
+#[tauri::command]
+pub async fn create_exec_session(
+    app_handle: tauri::AppHandle,
+    context: &str,
+    namespace: &str,
+    pod: &str,
+    container: &str,
+    command: Vec<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
+) -> Result<String, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let pod_api: Api<Pod> = Api::namespaced(client, namespace);
+
+    let mut attach_params = AttachParams::interactive_tty();
+    if !container.is_empty() {
+        attach_params = attach_params.container(container);
+    }
+
+    let session_id = Uuid::new_v4().to_string();
+    let info = TtySessionInfo {
+        session_id: session_id.clone(),
+        kind: TtySessionKind::Exec,
+        command: command.clone(),
+        context: Some(context.to_string()),
+        namespace: Some(namespace.to_string()),
+        pod: Some(pod.to_string()),
+        container: Some(container.to_string()).filter(|container| !container.is_empty()),
+    };
+
+    let mut attached = pod_api
+        .exec(pod, command, &attach_params)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    let (stdin_sender, mut stdin_receiver) = unbounded::<Vec<u8>>();
+    let (resize_sender, mut resize_receiver) =
+        watch::channel((rows.unwrap_or(DEFAULT_ROWS), cols.unwrap_or(DEFAULT_COLS)));
+    let (attached_sender, mut attached_receiver) = watch::channel(false);
+
+    // The session is registered before the tasks start, so a command that
+    // exits right away is still cleaned up.
+    let mut sessions = TTY_SESSIONS.lock().unwrap();
+    let mut tasks = Vec::new();
+
+    // Sizes that arrive while one is being sent collapse into the latest, so
+    // the terminal always ends up at the size of the view.
+    if let Some(mut terminal_size) = attached.terminal_size() {
+        tasks.push(tauri::async_runtime::spawn(async move {
+            loop {
+                let (height, width) = *resize_receiver.borrow_and_update();
+                if terminal_size.send(TerminalSize { height, width }).await.is_err() {
+                    break;
+                }
+                if resize_receiver.changed().await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    if let Some(mut stdin) = attached.stdin() {
+        tasks.push(tauri::async_runtime::spawn(async move {
+            while let Some(data) = stdin_receiver.next().await {
+                if stdin.write_all(&data).await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    let mut stdout = attached.stdout();
+    let status = attached.take_status();
+    let thread_session_id = session_id.clone();
+    tasks.push(tauri::async_runtime::spawn(async move {
+        let event = format!("tty_data_{}", thread_session_id);
+        let mut pending = Vec::new();
+        let mut listening = false;
+
+        if let Some(stdout) = stdout.as_mut() {
+            let mut buffer = vec![0u8; 8192];
+            loop {
+                tokio::select! {
+                    read = stdout.read(&mut buffer) => match read {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => pending.extend_from_slice(&buffer[..read]),
+                    },
+                    changed = attached_receiver.changed(), if !listening => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    },
+                }
+
+                listening = *attached_receiver.borrow();
+                if listening && !pending.is_empty() {
+                    let _ = app_handle.emit_all(event.as_ref(), std::mem::take(&mut pending));
+                }
+            }
+        }
+
+        while !*attached_receiver.borrow_and_update() {
+            if attached_receiver.changed().await.is_err() {
+                break;
+            }
+        }
+        if !pending.is_empty() {
+            let _ = app_handle.emit_all(event.as_ref(), pending);
+        }
+
+        let exit = match status {
+            Some(status) => exit_of(status.await),
+            None => exit_of(None),
+        };
+        remove_session(&thread_session_id);
+        emit_exit(&app_handle, &thread_session_id, exit);
+        let _ = attached.join().await;
+    }));
+
+    sessions.get_or_insert_with(HashMap::new).insert(
+        session_id.clone(),
+        TtySession {
+            info,
+            terminal: TerminalSession::Exec {
+                stdin: stdin_sender,
+                resize: resize_sender,
+                attached: attached_sender,
+                tasks,
+            },
+        },
+    );
+
+    return Ok(session_id);
+}
+
+/// Asks the shell of a session to exit, and kills the session when it is
+/// still running after `STOP_TIMEOUT`, for example because another program
+/// had the terminal.
+#[tauri::command]
+pub fn stop_tty_session(app_handle: tauri::AppHandle, session_id: &str) {
+    // write to pty to kill the process, this can be a bash or powershell command
+    write_to_pty(session_id, "exit\n");
+
+    let session_id = session_id.to_string();
+    thread::spawn(move || {
+        thread::sleep(STOP_TIMEOUT);
+
+        let running = TTY_SESSIONS
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|sessions| sessions.contains_key(&session_id));
+        if running {
+            kill_tty_session(app_handle, &session_id);
+        }
+    });
+}
+
+/// Ends a session right away. A PTY child is killed and reports its exit
+/// through its wait thread; an exec session has no remote kill, so closing
+/// the connection hangs up its terminal.
+#[tauri::command]
+pub fn kill_tty_session(app_handle: tauri::AppHandle, session_id: &str) {
+    let mut sessions_lock = TTY_SESSIONS.lock().unwrap();
+    let session = sessions_lock.as_mut().and_then(|sessions| sessions.get_mut(session_id));
+
+    match session.map(|session| &mut session.terminal) {
+        Some(TerminalSession::Pty { killer, .. }) => {
+            let _ = killer.kill();
+        }
+        Some(TerminalSession::Exec { .. }) => {
+            if let Some(TtySession {
+                terminal: TerminalSession::Exec { tasks, .. },
+                ..
+            }) = sessions_lock.as_mut().and_then(|sessions| sessions.remove(session_id))
+            {
+                tasks.iter().for_each(|task| task.abort());
+            }
+            drop(sessions_lock);
+
+            let exit = TtyExit {
+                exit_code: None,
+                message: Some("Session was killed".to_string()),
+            };
+            emit_exit(&app_handle, session_id, exit);
+        }
+        None => {}
+    }
+}
+
+#[tauri::command]
+pub fn list_tty_sessions() -> Vec<TtySessionInfo> {
+    return TTY_SESSIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|sessions| sessions.values())
+        .map(|session| session.info.clone())
+        .collect();
+}
+
+#[tauri::command]
+pub fn write_to_pty(session_id: &str, data: &str) {
+    // First, lock the sessions map
+    let sessions_lock = TTY_SESSIONS.lock().unwrap();
+
+    // Then, try to get the session from the map
+    if let Some(sessions) = sessions_lock.as_ref() {
+        let terminal = sessions.get(session_id).map(|session| &session.terminal);
+        if let Some(TerminalSession::Pty { writer, .. }) = terminal {
+            // Lock the writer
+            let mut writer_guard = writer.lock().unwrap();
+            // Attempt to write and handle any error
+            if write!(&mut *writer_guard, "{}", data).is_err() {
+                // Handle the error from the write! macro here
+            }
+        } else if let Some(TerminalSession::Exec { stdin, attached, .. }) = terminal {
+            attached.send_replace(true);
+            let _ = stdin.unbounded_send(data.as_bytes().to_vec());
+        } else {
+            // Handle the case when the session is not found
+        }
+    } else {
+        // Handle the case when the TTY_SESSIONS map is not initialized
+    }
+}
+
+/// Resizes the terminal of a session, so full screen programs redraw for the
+/// size of the terminal view.
+#[tauri::command]
+pub fn resize_tty(session_id: &str, rows: u16, cols: u16) {
+    let mut sessions_lock = TTY_SESSIONS.lock().unwrap();
+
+    let session = sessions_lock.as_mut().and_then(|sessions| sessions.get_mut(session_id));
+    match session.map(|session| &mut session.terminal) {
+        Some(TerminalSession::Pty { master, .. }) => {
+            let _ = master.resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            });
+        }
+        Some(TerminalSession::Exec { resize, attached, .. }) => {
+            attached.send_replace(true);
+            resize.send_replace((rows, cols));
+        }
+        _ => {}
+    }
+}
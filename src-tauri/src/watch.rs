@@ -0,0 +1,335 @@
+use crate::dynamic::{dynamic_api, resolve_api_resource};
+use crate::{client_with_context, SerializableKubeError};
+use futures::StreamExt;
+use istio_api_rs::networking::v1beta1::virtual_service::VirtualService;
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{
+    ConfigMap, Namespace, PersistentVolume, PersistentVolumeClaim, Pod, Secret, Service,
+};
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{Api, DynamicObject};
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::{Client, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+
+/// Payload of the `watch_event_<subscription_id>` events, shaped like the
+/// `kubectl get -w --output-watch-events` output the UI used to parse.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", content = "object", rename_all = "UPPERCASE")]
+pub enum WatchEvent<K> {
+    Added(K),
+    Modified(K),
+    Deleted(K),
+    Error(SerializableKubeError),
+}
+
+static WATCHES: Mutex<Option<HashMap<String, JoinHandle<()>>>> = Mutex::new(None);
+
+fn object_key<K: Resource>(object: &K) -> String {
+    object
+        .uid()
+        .unwrap_or_else(|| format!("{}/{}", object.namespace().unwrap_or_default(), object.name_any()))
+}
+
+fn object_version<K: Resource>(object: &K) -> Option<String> {
+    object.resource_version()
+}
+
+/// Returns a namespaced api, or a cluster-wide one when no namespace is selected.
+pub fn namespaced_or_all<K>(client: Client, namespace: &str) -> Api<K>
+where
+    K: Resource<Scope = NamespaceResourceScope>,
+    <K as Resource>::DynamicType: Default,
+{
+    if namespace.is_empty() {
+        return Api::all(client);
+    }
+
+    return Api::namespaced(client, namespace);
+}
+
+/// Starts a watcher for `api` that emits `WatchEvent`s until `unwatch` is called.
+///
+/// `kube::runtime::watcher` only reports applied objects and full relists, so the
+/// last seen version of every object is kept to tell additions from modifications
+/// and to emit deletions for objects that disappeared while the watch was down.
+pub fn spawn_watch<K>(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    api: Api<K>,
+    config: watcher::Config,
+) where
+    K: Resource + Clone + DeserializeOwned + Serialize + Debug + Send + Sync + 'static,
+{
+    let event_name = format!("watch_event_{}", subscription_id);
+
+    let handle = tauri::async_runtime::spawn(async move {
+        let mut known: HashMap<String, K> = HashMap::new();
+        let mut stream = watcher(api, config).default_backoff().boxed();
+
+        while let Some(event) = stream.next().await {
+            let events = match event {
+                Ok(watcher::Event::Applied(object)) => {
+                    match known.insert(object_key(&object), object.clone()) {
+                        Some(_) => vec![WatchEvent::Modified(object)],
+                        None => vec![WatchEvent::Added(object)],
+                    }
+                }
+                Ok(watcher::Event::Deleted(object)) => {
+                    known.remove(&object_key(&object));
+                    vec![WatchEvent::Deleted(object)]
+                }
+                Ok(watcher::Event::Restarted(objects)) => {
+                    let mut previous = std::mem::take(&mut known);
+                    let mut events = Vec::new();
+
+                    for object in objects {
+                        let key = object_key(&object);
+                        match previous.remove(&key) {
+                            Some(old) if object_version(&old) == object_version(&object) => {}
+                            Some(_) => events.push(WatchEvent::Modified(object.clone())),
+                            None => events.push(WatchEvent::Added(object.clone())),
+                        }
+                        known.insert(key, object);
+                    }
+
+                    events.extend(previous.into_values().map(WatchEvent::Deleted));
+                    events
+                }
+                Err(err) => vec![WatchEvent::Error(SerializableKubeError::from(err))],
+            };
+
+            for event in events {
+                if app_handle.emit_all(&event_name, event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    register_watch(subscription_id, handle);
+}
+
+/// Tracks a background task under `subscription_id` so `unwatch` can stop it,
+/// replacing any task that was registered under the same id.
+pub fn register_watch(subscription_id: &str, handle: JoinHandle<()>) {
+    let mut watches = WATCHES.lock().unwrap();
+    if let Some(previous) = watches
+        .get_or_insert_with(HashMap::new)
+        .insert(subscription_id.to_string(), handle)
+    {
+        previous.abort();
+    }
+}
+
+#[tauri::command]
+pub fn unwatch(subscription_id: &str) {
+    if let Some(watches) = WATCHES.lock().unwrap().as_mut() {
+        if let Some(handle) = watches.remove(subscription_id) {
+            handle.abort();
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn watch_namespaces(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let namespace_api: Api<Namespace> = Api::all(client);
+
+    spawn_watch(app_handle, subscription_id, namespace_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_pods(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    label_selector: &str,
+    field_selector: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let pod_api: Api<Pod> = namespaced_or_all(client, namespace);
+
+    let mut config = watcher::Config::default();
+    if !label_selector.is_empty() {
+        config = config.labels(label_selector);
+    }
+    if !field_selector.is_empty() {
+        config = config.fields(field_selector);
+    }
+
+    spawn_watch(app_handle, subscription_id, pod_api, config);
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_deployments(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let deployment_api: Api<Deployment> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, deployment_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_jobs(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let jobs_api: Api<Job> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, jobs_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_cronjobs(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let cronjobs_api: Api<CronJob> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, cronjobs_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_configmaps(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let configmaps_api: Api<ConfigMap> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, configmaps_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_secrets(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let secrets_api: Api<Secret> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, secrets_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_services(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let services_api: Api<Service> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, services_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_virtual_services(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let virtual_services_api: Api<VirtualService> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, virtual_services_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_ingresses(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let ingress_api: Api<Ingress> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, ingress_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_persistentvolumes(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let pv_api: Api<PersistentVolume> = Api::all(client);
+
+    spawn_watch(app_handle, subscription_id, pv_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_persistentvolumeclaims(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let pvc_api: Api<PersistentVolumeClaim> = namespaced_or_all(client, namespace);
+
+    spawn_watch(app_handle, subscription_id, pvc_api, watcher::Config::default());
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn watch_resource(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    group: &str,
+    version: &str,
+    kind: &str,
+    namespace: &str,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let (resource, capabilities) =
+        resolve_api_resource(&client, context, group, version, kind).await?;
+    let resource_api: Api<DynamicObject> = dynamic_api(client, &resource, &capabilities, namespace);
+
+    spawn_watch(app_handle, subscription_id, resource_api, watcher::Config::default());
+    return Ok(());
+}
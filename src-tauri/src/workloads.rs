@@ -0,0 +1,504 @@
+use crate::dynamic::{scale_resource, ScaleResult};
+use crate::rollout::{
+    daemonset_rollout_status, label_selector_string, replicaset_rollout_status,
+    spawn_rollout_status, statefulset_rollout_status,
+};
+use crate::{client_with_context, ErrorKind, SerializableKubeError};
+use k8s_openapi::api::apps::v1::{DaemonSet, ReplicaSet, StatefulSet};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, DeleteParams, ListParams, Patch, PatchParams};
+use kube::ResourceExt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const POD_RESTART_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[tauri::command]
+pub async fn list_statefulsets(
+    context: &str,
+    namespace: &str,
+) -> Result<Vec<StatefulSet>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    return statefulset_api
+        .list(&ListParams::default())
+        .await
+        .map(|statefulsets| statefulsets.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn get_statefulset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<StatefulSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    return statefulset_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn replace_statefulset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    object: StatefulSet,
+) -> Result<StatefulSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    return statefulset_api
+        .replace(name, &Default::default(), &object)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn restart_statefulset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<bool, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    return statefulset_api
+        .restart(name)
+        .await
+        .map(|_statefulset| true)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn scale_statefulset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    replicas: i32,
+    force: bool,
+) -> Result<ScaleResult, SerializableKubeError> {
+    return scale_resource(context, "apps", "v1", "StatefulSet", namespace, name, replicas, force).await;
+}
+
+/// Sets the rolling update partition of a StatefulSet. Only pods with an
+/// ordinal at or above the partition are updated, so lowering it step by step
+/// gives a staged rollout. StatefulSets using the `OnDelete` strategy are
+/// rejected rather than switched over.
+#[tauri::command]
+pub async fn set_statefulset_partition(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    partition: i32,
+) -> Result<StatefulSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    let statefulset = statefulset_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+    let strategy = statefulset
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.update_strategy.as_ref())
+        .and_then(|strategy| strategy.type_.clone())
+        .unwrap_or("RollingUpdate".to_string());
+    if strategy != "RollingUpdate" {
+        return Err(SerializableKubeError::new(
+            ErrorKind::Invalid,
+            format!(
+                "StatefulSet {} uses the {} update strategy, partitions only apply to RollingUpdate",
+                name, strategy
+            ),
+        ));
+    }
+
+    let patch = serde_json::json!({
+        "spec": {
+            "updateStrategy": {
+                "rollingUpdate": { "partition": partition },
+            },
+        },
+    });
+
+    return statefulset_api
+        .patch(name, &PatchParams::default(), &Patch::Strategic(patch))
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn watch_statefulset_rollout_status(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    name: &str,
+    timeout_seconds: Option<u64>,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let statefulset_api: Api<StatefulSet> = Api::namespaced(client, namespace);
+
+    spawn_rollout_status(
+        app_handle,
+        subscription_id,
+        statefulset_api,
+        name,
+        timeout_seconds,
+        statefulset_rollout_status,
+    );
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn list_daemonsets(
+    context: &str,
+    namespace: &str,
+) -> Result<Vec<DaemonSet>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let daemonset_api: Api<DaemonSet> = Api::namespaced(client, namespace);
+
+    return daemonset_api
+        .list(&ListParams::default())
+        .await
+        .map(|daemonsets| daemonsets.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn get_daemonset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<DaemonSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let daemonset_api: Api<DaemonSet> = Api::namespaced(client, namespace);
+
+    return daemonset_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn replace_daemonset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    object: DaemonSet,
+) -> Result<DaemonSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let daemonset_api: Api<DaemonSet> = Api::namespaced(client, namespace);
+
+    return daemonset_api
+        .replace(name, &Default::default(), &object)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn restart_daemonset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<bool, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let daemonset_api: Api<DaemonSet> = Api::namespaced(client, namespace);
+
+    return daemonset_api
+        .restart(name)
+        .await
+        .map(|_daemonset| true)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn watch_daemonset_rollout_status(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    name: &str,
+    timeout_seconds: Option<u64>,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let daemonset_api: Api<DaemonSet> = Api::namespaced(client, namespace);
+
+    spawn_rollout_status(
+        app_handle,
+        subscription_id,
+        daemonset_api,
+        name,
+        timeout_seconds,
+        daemonset_rollout_status,
+    );
+    return Ok(());
+}
+
+#[tauri::command]
+pub async fn list_replicasets(
+    context: &str,
+    namespace: &str,
+) -> Result<Vec<ReplicaSet>, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let replicaset_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    return replicaset_api
+        .list(&ListParams::default())
+        .await
+        .map(|replicasets| replicasets.items)
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn get_replicaset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<ReplicaSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let replicaset_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    return replicaset_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+#[tauri::command]
+pub async fn replace_replicaset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    object: ReplicaSet,
+) -> Result<ReplicaSet, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let replicaset_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    return replicaset_api
+        .replace(name, &Default::default(), &object)
+        .await
+        .map_err(|err| SerializableKubeError::from(err));
+}
+
+fn is_ready(pod: &Pod) -> bool {
+    return pod
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .iter()
+        .flat_map(|conditions| conditions.iter())
+        .any(|condition| condition.type_ == "Ready" && condition.status == "True");
+}
+
+async fn owned_pods(
+    pod_api: &Api<Pod>,
+    replicaset: &ReplicaSet,
+    selector: &str,
+) -> Result<Vec<Pod>, SerializableKubeError> {
+    let pods = pod_api
+        .list(&ListParams::default().labels(selector))
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+
+    return Ok(pods
+        .items
+        .into_iter()
+        .filter(|pod| {
+            pod.owner_references()
+                .iter()
+                .any(|owner| Some(&owner.uid) == replicaset.metadata.uid.as_ref())
+        })
+        .collect());
+}
+
+fn ready_count(pods: &[Pod]) -> usize {
+    return pods
+        .iter()
+        .filter(|pod| pod.metadata.deletion_timestamp.is_none() && is_ready(pod))
+        .count();
+}
+
+/// Whether the pod with `deleted_uid` is gone and the ReplicaSet is back to
+/// the number of ready pods it had before that pod was deleted.
+fn is_replaced(pods: &[Pod], deleted_uid: &str, ready_before: usize) -> bool {
+    let gone = pods
+        .iter()
+        .all(|pod| pod.metadata.uid.as_deref() != Some(deleted_uid));
+    return gone && ready_count(pods) >= ready_before;
+}
+
+async fn wait_for_replacement(
+    pod_api: &Api<Pod>,
+    replicaset: &ReplicaSet,
+    selector: &str,
+    deleted: &Pod,
+    ready_before: usize,
+) -> Result<(), SerializableKubeError> {
+    let deleted_uid = deleted.uid().unwrap_or_default();
+    let deadline = Instant::now() + POD_RESTART_TIMEOUT;
+
+    while Instant::now() < deadline {
+        let pods = owned_pods(pod_api, replicaset, selector).await?;
+        if is_replaced(&pods, &deleted_uid, ready_before) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    return Err(SerializableKubeError::new(
+        ErrorKind::Timeout,
+        format!("Timed out waiting for a replacement of pod {} to become ready", deleted.name_any()),
+    ));
+}
+
+/// Bumps the pod-template restart annotation like the other workload
+/// restarts. A bare ReplicaSet does not roll when its template changes, so
+/// the pods it already runs are then replaced one at a time, each time
+/// waiting until the ReplicaSet is back to the ready count it had before
+/// the deletion. ReplicaSets managed by a Deployment are rejected: changing
+/// their template would make the Deployment start a rollout of its own.
+#[tauri::command]
+pub async fn restart_replicaset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<bool, SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let replicaset_api: Api<ReplicaSet> = Api::namespaced(client.clone(), namespace);
+    let pod_api: Api<Pod> = Api::namespaced(client, namespace);
+
+    let replicaset = replicaset_api
+        .get(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+    if let Some(owner) = replicaset.owner_references().iter().find(|owner| owner.controller == Some(true)) {
+        return Err(SerializableKubeError::new(
+            ErrorKind::Invalid,
+            format!(
+                "ReplicaSet {} is managed by {} {}, restart that instead",
+                name, owner.kind, owner.name
+            ),
+        ));
+    }
+
+    let replicaset = replicaset_api
+        .restart(name)
+        .await
+        .map_err(|err| SerializableKubeError::from(err))?;
+    let selector = replicaset
+        .spec
+        .as_ref()
+        .map(|spec| label_selector_string(&spec.selector))
+        .unwrap_or_default();
+
+    let pods = owned_pods(&pod_api, &replicaset, &selector).await?;
+    for pod in pods.iter().filter(|pod| pod.metadata.deletion_timestamp.is_none()) {
+        let ready_before = ready_count(&owned_pods(&pod_api, &replicaset, &selector).await?);
+        match pod_api.delete(&pod.name_any(), &DeleteParams::default()).await {
+            Ok(_) => {}
+            Err(kube::Error::Api(error)) if error.code == 404 => {}
+            Err(err) => return Err(SerializableKubeError::from(err)),
+        }
+
+        wait_for_replacement(&pod_api, &replicaset, &selector, pod, ready_before).await?;
+    }
+
+    return Ok(true);
+}
+
+#[tauri::command]
+pub async fn scale_replicaset(
+    context: &str,
+    namespace: &str,
+    name: &str,
+    replicas: i32,
+    force: bool,
+) -> Result<ScaleResult, SerializableKubeError> {
+    return scale_resource(context, "apps", "v1", "ReplicaSet", namespace, name, replicas, force).await;
+}
+
+#[tauri::command]
+pub async fn watch_replicaset_rollout_status(
+    app_handle: tauri::AppHandle,
+    subscription_id: &str,
+    context: &str,
+    namespace: &str,
+    name: &str,
+    timeout_seconds: Option<u64>,
+) -> Result<(), SerializableKubeError> {
+    let client = client_with_context(context).await?;
+    let replicaset_api: Api<ReplicaSet> = Api::namespaced(client, namespace);
+
+    spawn_rollout_status(
+        app_handle,
+        subscription_id,
+        replicaset_api,
+        name,
+        timeout_seconds,
+        replicaset_rollout_status,
+    );
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(uid: &str, ready: bool, terminating: bool) -> Pod {
+        let mut pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": uid, "uid": uid },
+            "status": {
+                "conditions": [{ "type": "Ready", "status": if ready { "True" } else { "False" } }],
+            },
+        }))
+        .unwrap();
+        if terminating {
+            pod.metadata.deletion_timestamp = Some(k8s_openapi::apimachinery::pkg::apis::meta::v1::Time(
+                k8s_openapi::chrono::Utc::now(),
+            ));
+        }
+        return pod;
+    }
+
+    #[test]
+    fn ready_count_skips_terminating_and_unready_pods() {
+        let pods = vec![pod("a", true, false), pod("b", true, true), pod("c", false, false)];
+
+        assert_eq!(ready_count(&pods), 1);
+    }
+
+    #[test]
+    fn replacement_waits_for_the_deleted_pod_to_go() {
+        let pods = vec![pod("a", true, true), pod("b", true, false), pod("c", true, false)];
+
+        assert!(!is_replaced(&pods, "a", 2));
+    }
+
+    #[test]
+    fn replacement_compares_against_the_ready_count_before_deletion() {
+        // Two of three pods were ready; the replacement of a ready pod must
+        // become ready before the next deletion.
+        let pods = vec![pod("b", true, false), pod("d", false, false)];
+        assert!(!is_replaced(&pods, "a", 2));
+
+        let pods = vec![pod("b", true, false), pod("d", true, false)];
+        assert!(is_replaced(&pods, "a", 2));
+    }
+
+    #[test]
+    fn replacing_an_unready_pod_does_not_wait_for_full_readiness() {
+        // The ReplicaSet wants three pods but only one was ready, so deleting
+        // the unready pod must not block until all three are ready.
+        let pods = vec![pod("a", true, false), pod("c", false, false), pod("d", false, false)];
+
+        assert!(is_replaced(&pods, "b", 1));
+    }
+}
@@ -54,6 +54,7 @@ export function getDefaultActions<T extends KubernetesObject | VirtualService>(
           {
             context: context,
             namespace: row.metadata?.namespace,
+            apiVersion: row.apiVersion,
             type: row.kind,
             name: row.metadata?.name,
           },
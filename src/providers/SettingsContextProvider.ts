@@ -14,6 +14,8 @@ export const SettingsContextStateKey: InjectionKey<
 export interface ContextSettings {
   context: string;
   namespaces: string[];
+  oidcFlow?: "authorizationCode" | "deviceCode";
+  oidcLoopbackPort?: number;
 }
 
 export interface SettingsContextState {
@@ -36,6 +38,8 @@ export interface SettingsContextState {
     };
     client: {
       currentKubeConfig?: string | null
+      kubeConfigGlob?: string | null
+      nativeEksAuth: boolean
     }
   };
 }
@@ -65,6 +69,8 @@ export default {
         },
         client: {
           currentKubeConfig: null,
+          kubeConfigGlob: null,
+          nativeEksAuth: false,
         }
       },
     });
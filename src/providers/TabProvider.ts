@@ -6,7 +6,9 @@ import {
   toRefs,
   ToRefs,
   shallowRef,
+  defineAsyncComponent,
 } from "vue";
+import { Kubernetes } from "@/services/Kubernetes";
 
 export const TabProviderStateKey: InjectionKey<ToRefs<TabProviderState>> =
   Symbol("TabProviderState");
@@ -80,6 +82,32 @@ export default {
       }
     };
 
+    // Tabs don't survive a reload of the webview, but the sessions of their
+    // shells do, so those get their tabs back. Local terminals have no view
+    // to come back to.
+    Kubernetes.listTtySessions().then((sessions) => {
+      sessions.forEach((session) => {
+        if (session.kind !== "exec" || !session.context || !session.pod) {
+          Kubernetes.killTtySession(session.sessionId);
+          return;
+        }
+
+        addTab(
+          `shell_${session.pod}_${session.container ?? ""}`,
+          session.container ? `${session.pod}/${session.container}` : session.pod,
+          defineAsyncComponent(() => import("@/views/Shell.vue")),
+          {
+            context: session.context,
+            namespace: session.namespace,
+            pod: { metadata: { name: session.pod, namespace: session.namespace } },
+            container: session.container ? { name: session.container } : undefined,
+            sessionId: session.sessionId,
+          },
+          "shell"
+        );
+      });
+    });
+
     provide(TabProviderAddTabKey, addTab);
     provide(TabProviderCloseTabKey, closeTab);
   },
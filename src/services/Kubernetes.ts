@@ -1,67 +1,385 @@
 import {
   KubernetesObject,
+  NodeMetric,
   PodMetric,
   V1APIGroup,
   V1APIResource,
   V1ConfigMap,
   V1CronJob,
+  V1DaemonSet,
   V1Deployment,
   V1Ingress,
   V1Job,
   V1Namespace,
+  V1Node,
   V1PersistentVolumeClaim,
   V1Pod,
+  V1ReplicaSet,
   V1Secret,
   V1Service,
+  V1StatefulSet,
 } from "@kubernetes/client-node";
 import { VirtualService } from "@kubernetes-models/istio/networking.istio.io/v1beta1";
-import { invoke } from "@tauri-apps/api/tauri";
-import { Command } from "@tauri-apps/api/shell";
+import { invoke as tauriInvoke, InvokeArgs } from "@tauri-apps/api/tauri";
+import { listen } from "@tauri-apps/api/event";
+
+/** Commands that log in themselves, and so are never retried after one. */
+const LOGIN_COMMANDS = [
+  "get_login_provider",
+  "refresh_credentials",
+  "login_oidc",
+  "cancel_oidc_login",
+];
+
+/** One credential refresh per context, shared by every call that needs it. */
+const pendingRefreshes = new Map<string, Promise<unknown>>();
+
+/**
+ * Invokes a command and, when it fails because the credentials of its
+ * context expired, logs in again and retries it once. A login that fails
+ * or can't be done from here leaves the original error to the caller.
+ */
+async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args);
+  } catch (error: any) {
+    const context = (args as { context?: string } | undefined)?.context;
+    if (
+      error?.kind !== "AuthExpired" ||
+      !context ||
+      LOGIN_COMMANDS.includes(command)
+    ) {
+      throw error;
+    }
+
+    let refresh = pendingRefreshes.get(context);
+    if (!refresh) {
+      refresh = tauriInvoke("refresh_credentials", { context: context }).finally(
+        () => pendingRefreshes.delete(context)
+      );
+      pendingRefreshes.set(context, refresh);
+    }
+
+    try {
+      await refresh;
+    } catch {
+      throw error;
+    }
+
+    return tauriInvoke<T>(command, args);
+  }
+}
+
+export type KubernetesErrorKind =
+  | "Unknown"
+  | "SettingsMissing"
+  | "SettingsInvalid"
+  | "KubeconfigNotFound"
+  | "KubeconfigInvalid"
+  | "NoCurrentContext"
+  | "ContextNotFound"
+  | "AuthExpired"
+  | "Forbidden"
+  | "NotFound"
+  | "Conflict"
+  | "Invalid"
+  | "ConnectionRefused"
+  | "Tls"
+  | "Timeout"
+  | "ExecPluginFailed"
+  | "DiscoveryFailed"
+  | "Cancelled";
 
 export interface KubernetesError {
+  kind: KubernetesErrorKind;
   message: string;
   code: number;
   reason: string;
   details: any;
 }
 
+export interface FieldConflict {
+  manager: string;
+  field: string;
+}
+
+export interface DiffEntry {
+  path: string;
+  operation: "added" | "removed" | "changed";
+  old: unknown | null;
+  new: unknown | null;
+}
+
+export interface ChangePreview {
+  live: KubernetesObject;
+  result: KubernetesObject;
+  diff: DiffEntry[];
+}
+
+export interface RolloutRevision {
+  revision: number;
+  replicaSet: string;
+  changeCause: string | null;
+  createdAt: string | null;
+  replicas: number;
+  current: boolean;
+  template: unknown;
+  changes: DiffEntry[];
+}
+
+export type RolloutPhase = "Progressing" | "Complete" | "Failed" | "TimedOut";
+
+export interface RolloutStatus {
+  phase: RolloutPhase;
+  message: string;
+  replicas: number;
+  updatedReplicas: number;
+  readyReplicas: number;
+  availableReplicas: number;
+  progressing: {
+    status: string;
+    reason: string | null;
+    message: string | null;
+  } | null;
+  partition: number | null;
+}
+
+export type RolloutEvent =
+  | { type: "STATUS"; object: RolloutStatus }
+  | { type: "ERROR"; object: KubernetesError };
+
+export interface ScaleResult {
+  previousReplicas: number;
+  replicas: number;
+}
+
+export interface DrainProgress {
+  namespace: string;
+  name: string;
+  status: "Skipped" | "Evicting" | "Blocked" | "Evicted" | "Failed";
+  reason: string | null;
+  disruptionBudget: string | null;
+}
+
+export interface DrainResult {
+  evicted: DrainProgress[];
+  skipped: DrainProgress[];
+  blocked: DrainProgress[];
+  failed: DrainProgress[];
+}
+
+export interface DrainOptions {
+  force?: boolean;
+  deleteEmptydirData?: boolean;
+  gracePeriodSeconds?: number;
+  timeoutSeconds?: number;
+}
+
+export interface EventSummary {
+  key: string;
+  type: string | null;
+  reason: string | null;
+  note: string | null;
+  regarding: {
+    kind?: string;
+    name?: string;
+    namespace?: string;
+    uid?: string;
+    fieldPath?: string;
+  } | null;
+  reportingController: string | null;
+  count: number;
+  firstSeen: string | null;
+  lastSeen: string | null;
+}
+
+export interface EventFilter {
+  kind?: string;
+  name?: string;
+  uid?: string;
+}
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error" | "fatal";
+
+export interface LogLine {
+  pod: string;
+  container: string | null;
+  timestamp: string | null;
+  message: string;
+  level: LogLevel | null;
+  fields?: Record<string, string>;
+}
+
+export interface LogFilter {
+  include?: string;
+  exclude?: string;
+  minLevel?: LogLevel;
+  fields?: Record<string, string>;
+}
+
+export interface LogSource {
+  pod: string;
+  container: string;
+}
+
+export interface LogTarget {
+  labelSelector?: string;
+  ownerKind?: string;
+  ownerName?: string;
+}
+
+export type LogEvent =
+  | { type: "LINES"; object: LogLine[] }
+  | { type: "ATTACHED"; object: LogSource }
+  | { type: "DETACHED"; object: LogSource }
+  | { type: "ERROR"; object: KubernetesError }
+  | { type: "END" };
+
+export interface LogOptions {
+  container?: string;
+  previous?: boolean;
+  sinceSeconds?: number;
+  tailLines?: number;
+  timestamps?: boolean;
+  filter?: LogFilter;
+}
+
+export type LogExportFormat = "plain" | "jsonl";
+
+export interface LogExportTarget {
+  pod?: string;
+  labelSelector?: string;
+  ownerKind?: string;
+  ownerName?: string;
+}
+
+export interface LogExportOptions extends LogOptions {
+  format: LogExportFormat;
+  gzip?: boolean;
+  sinceTime?: string;
+  untilTime?: string;
+}
+
+export interface LogExportProgress {
+  pod: string;
+  container: string;
+  lines: number;
+  bytes: number;
+  done: boolean;
+}
+
+export interface LogExportFailure {
+  pod: string;
+  container: string;
+  message: string;
+}
+
+export interface LogExportResult {
+  path: string;
+  lines: number;
+  bytes: number;
+  failures: LogExportFailure[];
+}
+
+export interface TtyExit {
+  exitCode: number | null;
+  message: string | null;
+}
+
+export interface TtySessionInfo {
+  sessionId: string;
+  kind: "pty" | "exec";
+  command: string[];
+  context: string | null;
+  namespace: string | null;
+  pod: string | null;
+  container: string | null;
+}
+
+export interface DescribeTable {
+  columns: string[];
+  rows: string[][];
+}
+
+export interface DescribeSection {
+  title: string;
+  rows: { key: string; value: string }[];
+  tables: DescribeTable[];
+  sections: DescribeSection[];
+}
+
+export interface DescribeDocument {
+  kind: string;
+  name: string;
+  namespace: string | null;
+  sections: DescribeSection[];
+}
+
+export interface WatchEvent<T> {
+  type: "ADDED" | "MODIFIED" | "DELETED" | "ERROR";
+  object: T;
+}
+
+export interface ContextSource {
+  name: string;
+  cluster: string | null;
+  user: string | null;
+  namespace: string | null;
+  source: string | null;
+}
+
+export interface KubeconfigChangedEvent {
+  added: string[];
+  removed: string[];
+  modified: string[];
+}
+
+export interface LoginProvider {
+  provider:
+    | "awsSso"
+    | "gcloud"
+    | "azureCli"
+    | "azureDeveloperCli"
+    | "oidcLogin"
+    | "oidc";
+  profile?: string | null;
+  command?: string;
+  args?: string[];
+  issuerUrl?: string;
+}
+
+export type OidcFlow = "authorizationCode" | "deviceCode";
+
+export interface OidcLoginPrompt {
+  context: string;
+  url: string;
+  userCode: string | null;
+}
+
 export class Kubernetes {
   static async getAuthErrorHandler(
     context: string,
-    errorMessage: string
+    error: KubernetesError
   ): Promise<{
     canHandle: boolean;
-    callback: (authCompletedCallback?: () => void) => void;
+    callback: (
+      authCompletedCallback?: () => void,
+      authFailedCallback?: (error: KubernetesError) => void
+    ) => void;
   }> {
-    // AWS SSO
-    if (
-      errorMessage.includes("AWS_PROFILE") &&
-      errorMessage.includes("Error loading SSO Token")
-    ) {
-      const context_auth_info = (await invoke("get_context_auth_info", {
-        context: context,
-      })) as any;
-
-      let aws_profile = null;
-      try {
-        aws_profile = context_auth_info.user.exec.env.find(
-          (env: any) => env.name === "AWS_PROFILE"
-        ).value;
-      } catch {}
+    if (error.kind === "AuthExpired") {
+      const provider = await Kubernetes.getLoginProvider(context);
 
       return {
-        canHandle: aws_profile !== null,
-        callback: async (authCompletedCallback?) => {
-          const command = new Command("aws", [
-            "sso",
-            "login",
-            "--profile",
-            aws_profile,
-          ]);
-          command.addListener("close", async () => {
-            authCompletedCallback?.();
-          });
-          await command.spawn();
+        canHandle: provider !== null,
+        callback: async (authCompletedCallback?, authFailedCallback?) => {
+          try {
+            await Kubernetes.refreshCredentials(context);
+          } catch (error: any) {
+            authFailedCallback?.(error);
+            return;
+          }
+          authCompletedCallback?.();
         },
       };
     }
@@ -74,6 +392,36 @@ export class Kubernetes {
     };
   }
 
+  static async getLoginProvider(
+    context: string
+  ): Promise<LoginProvider | null> {
+    return invoke("get_login_provider", { context: context });
+  }
+
+  static async refreshCredentials(context: string): Promise<LoginProvider> {
+    return invoke("refresh_credentials", { context: context });
+  }
+
+  static async loginOidc(context: string, flow?: OidcFlow): Promise<string> {
+    return invoke("login_oidc", { context: context, flow: flow });
+  }
+
+  static async cancelOidcLogin(): Promise<void> {
+    return invoke("cancel_oidc_login", {});
+  }
+
+  static async logoutOidc(context: string): Promise<void> {
+    return invoke("logout_oidc", { context: context });
+  }
+
+  static async onOidcLoginPrompt(
+    callback: (prompt: OidcLoginPrompt) => void
+  ): Promise<() => void> {
+    return listen<OidcLoginPrompt>("oidc_login_prompt", (event) =>
+      callback(event.payload)
+    );
+  }
+
   static async getCurrentContext(): Promise<string> {
     return invoke("get_current_context", {});
   }
@@ -82,6 +430,30 @@ export class Kubernetes {
     return invoke("list_contexts", {});
   }
 
+  static async getContextSources(): Promise<ContextSource[]> {
+    return invoke("list_context_sources", {});
+  }
+
+  static async onKubeconfigChanged(
+    callback: (event: KubeconfigChangedEvent) => void
+  ): Promise<() => void> {
+    return listen<KubeconfigChangedEvent>("kubeconfig_changed", (event) =>
+      callback(event.payload)
+    );
+  }
+
+  static async onKubeconfigError(
+    callback: (error: KubernetesError) => void
+  ): Promise<() => void> {
+    return listen<KubernetesError>("kubeconfig_error", (event) =>
+      callback(event.payload)
+    );
+  }
+
+  static async resetClient(context?: string): Promise<void> {
+    return invoke("reset_client", { context: context });
+  }
+
   static async getNamespaces(context: string): Promise<V1Namespace[]> {
     return invoke("list_namespaces", { context: context });
   }
@@ -277,4 +649,635 @@ export class Kubernetes {
       namespace: namespace,
     });
   }
+
+  static async getStatefulSets(
+    context: string,
+    namespace: string
+  ): Promise<V1StatefulSet[]> {
+    return invoke("list_statefulsets", {
+      context: context,
+      namespace: namespace,
+    });
+  }
+
+  static async getStatefulSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<V1StatefulSet> {
+    return invoke("get_statefulset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async restartStatefulSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<boolean> {
+    return invoke("restart_statefulset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async scaleStatefulSet(
+    context: string,
+    namespace: string,
+    name: string,
+    replicas: number,
+    force = false
+  ): Promise<ScaleResult> {
+    return invoke("scale_statefulset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+      replicas: replicas,
+      force: force,
+    });
+  }
+
+  static async getDaemonSets(
+    context: string,
+    namespace: string
+  ): Promise<V1DaemonSet[]> {
+    return invoke("list_daemonsets", {
+      context: context,
+      namespace: namespace,
+    });
+  }
+
+  static async getDaemonSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<V1DaemonSet> {
+    return invoke("get_daemonset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async restartDaemonSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<boolean> {
+    return invoke("restart_daemonset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async getReplicaSets(
+    context: string,
+    namespace: string
+  ): Promise<V1ReplicaSet[]> {
+    return invoke("list_replicasets", {
+      context: context,
+      namespace: namespace,
+    });
+  }
+
+  static async getReplicaSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<V1ReplicaSet> {
+    return invoke("get_replicaset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async restartReplicaSet(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<boolean> {
+    return invoke("restart_replicaset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async scaleReplicaSet(
+    context: string,
+    namespace: string,
+    name: string,
+    replicas: number,
+    force = false
+  ): Promise<ScaleResult> {
+    return invoke("scale_replicaset", {
+      context: context,
+      namespace: namespace,
+      name: name,
+      replicas: replicas,
+      force: force,
+    });
+  }
+
+  static async setStatefulSetPartition(
+    context: string,
+    namespace: string,
+    name: string,
+    partition: number
+  ): Promise<V1StatefulSet> {
+    return invoke("set_statefulset_partition", {
+      context: context,
+      namespace: namespace,
+      name: name,
+      partition: partition,
+    });
+  }
+
+  static async getNodes(context: string): Promise<V1Node[]> {
+    return invoke("list_nodes", { context: context });
+  }
+
+  static async getNodeMetrics(context: string): Promise<NodeMetric[]> {
+    return invoke("get_node_metrics", { context: context });
+  }
+
+  static async cordonNode(context: string, name: string): Promise<V1Node> {
+    return invoke("cordon_node", { context: context, name: name });
+  }
+
+  static async uncordonNode(context: string, name: string): Promise<V1Node> {
+    return invoke("uncordon_node", { context: context, name: name });
+  }
+
+  static async drainNode(
+    context: string,
+    name: string,
+    options: DrainOptions,
+    onProgress: (progress: DrainProgress) => void
+  ): Promise<DrainResult> {
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<DrainProgress>(
+      `drain_progress_${subscriptionId}`,
+      (event) => onProgress(event.payload)
+    );
+
+    try {
+      return await invoke("drain_node", {
+        context: context,
+        name: name,
+        force: options.force ?? false,
+        deleteEmptydirData: options.deleteEmptydirData ?? false,
+        gracePeriodSeconds: options.gracePeriodSeconds,
+        timeoutSeconds: options.timeoutSeconds,
+        subscriptionId: subscriptionId,
+      });
+    } finally {
+      unlisten();
+    }
+  }
+
+  static async getRolloutHistory(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<RolloutRevision[]> {
+    return invoke("get_rollout_history", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async undoRollout(
+    context: string,
+    namespace: string,
+    name: string,
+    revision?: number
+  ): Promise<V1Deployment> {
+    return invoke("undo_rollout", {
+      context: context,
+      namespace: namespace,
+      name: name,
+      revision: revision,
+    });
+  }
+
+  static async pauseRollout(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<V1Deployment> {
+    return invoke("pause_rollout", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async resumeRollout(
+    context: string,
+    namespace: string,
+    name: string
+  ): Promise<V1Deployment> {
+    return invoke("resume_rollout", {
+      context: context,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async watchRolloutStatus(
+    context: string,
+    namespace: string,
+    name: string,
+    callback: (event: RolloutEvent) => void,
+    timeoutSeconds?: number,
+    kind: "Deployment" | "StatefulSet" | "DaemonSet" | "ReplicaSet" = "Deployment"
+  ): Promise<() => Promise<void>> {
+    const command =
+      kind === "Deployment"
+        ? "watch_rollout_status"
+        : `watch_${kind.toLowerCase()}_rollout_status`;
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<RolloutEvent>(
+      `rollout_status_${subscriptionId}`,
+      (event) => callback(event.payload)
+    );
+
+    try {
+      await invoke(command, {
+        context: context,
+        namespace: namespace,
+        name: name,
+        timeoutSeconds: timeoutSeconds,
+        subscriptionId: subscriptionId,
+      });
+    } catch (error) {
+      unlisten();
+      throw error;
+    }
+
+    return async () => {
+      unlisten();
+      await invoke("unwatch", { subscriptionId: subscriptionId });
+    };
+  }
+
+  static async getEvents(
+    context: string,
+    namespace: string,
+    filter: EventFilter = {}
+  ): Promise<EventSummary[]> {
+    return invoke("list_events", {
+      context: context,
+      namespace: namespace,
+      kind: filter.kind ?? "",
+      name: filter.name ?? "",
+      uid: filter.uid ?? "",
+    });
+  }
+
+  static async watchEvents(
+    context: string,
+    namespace: string,
+    filter: EventFilter,
+    callback: (event: WatchEvent<EventSummary>) => void
+  ): Promise<() => Promise<void>> {
+    return Kubernetes.watch<EventSummary>(
+      "watch_events",
+      {
+        context: context,
+        namespace: namespace,
+        kind: filter.kind ?? "",
+        name: filter.name ?? "",
+        uid: filter.uid ?? "",
+      },
+      callback
+    );
+  }
+
+  static async describeResource(
+    context: string,
+    apiVersion: string | undefined,
+    kind: string,
+    namespace: string,
+    name: string
+  ): Promise<DescribeDocument> {
+    const [group, version] = apiVersion?.includes("/")
+      ? apiVersion.split("/")
+      : ["", apiVersion ?? ""];
+
+    return invoke("describe_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace ?? "",
+      name: name,
+    });
+  }
+
+  static async streamLogs(
+    context: string,
+    namespace: string,
+    name: string,
+    options: LogOptions,
+    callback: (event: LogEvent) => void
+  ): Promise<() => Promise<void>> {
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<LogEvent>(`logs_${subscriptionId}`, (event) =>
+      callback(event.payload)
+    );
+
+    try {
+      await invoke("stream_logs", {
+        context: context,
+        namespace: namespace,
+        name: name,
+        options: options,
+        subscriptionId: subscriptionId,
+      });
+    } catch (error) {
+      unlisten();
+      throw error;
+    }
+
+    return async () => {
+      unlisten();
+      await invoke("stop_logs", { subscriptionId: subscriptionId });
+    };
+  }
+
+  static async streamAggregatedLogs(
+    context: string,
+    namespace: string,
+    target: LogTarget,
+    options: LogOptions,
+    callback: (event: LogEvent) => void
+  ): Promise<() => Promise<void>> {
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<LogEvent>(`logs_${subscriptionId}`, (event) =>
+      callback(event.payload)
+    );
+
+    try {
+      await invoke("stream_aggregated_logs", {
+        context: context,
+        namespace: namespace,
+        labelSelector: target.labelSelector ?? "",
+        ownerKind: target.ownerKind ?? "",
+        ownerName: target.ownerName ?? "",
+        options: options,
+        subscriptionId: subscriptionId,
+      });
+    } catch (error) {
+      unlisten();
+      throw error;
+    }
+
+    return async () => {
+      unlisten();
+      await invoke("stop_logs", { subscriptionId: subscriptionId });
+    };
+  }
+
+  static async exportLogs(
+    context: string,
+    namespace: string,
+    target: LogExportTarget,
+    path: string,
+    options: LogExportOptions,
+    onProgress: (progress: LogExportProgress) => void
+  ): Promise<LogExportResult> {
+    const { format, gzip, sinceTime, untilTime, ...logOptions } = options;
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<LogExportProgress>(
+      `log_export_progress_${subscriptionId}`,
+      (event) => onProgress(event.payload)
+    );
+
+    try {
+      return await invoke("export_logs", {
+        context: context,
+        namespace: namespace,
+        target: target,
+        path: path,
+        format: format,
+        gzip: gzip ?? false,
+        sinceTime: sinceTime,
+        untilTime: untilTime,
+        options: logOptions,
+        subscriptionId: subscriptionId,
+      });
+    } finally {
+      unlisten();
+    }
+  }
+
+  static async listTtySessions(): Promise<TtySessionInfo[]> {
+    return invoke("list_tty_sessions");
+  }
+
+  static async killTtySession(sessionId: string): Promise<void> {
+    return invoke("kill_tty_session", { sessionId: sessionId });
+  }
+
+  static async watch<T>(
+    command: string,
+    args: Record<string, unknown>,
+    callback: (event: WatchEvent<T>) => void
+  ): Promise<() => Promise<void>> {
+    const subscriptionId = crypto.randomUUID();
+    const unlisten = await listen<WatchEvent<T>>(
+      `watch_event_${subscriptionId}`,
+      (event) => callback(event.payload)
+    );
+
+    try {
+      await invoke(command, { ...args, subscriptionId: subscriptionId });
+    } catch (error) {
+      unlisten();
+      throw error;
+    }
+
+    return async () => {
+      unlisten();
+      await invoke("unwatch", { subscriptionId: subscriptionId });
+    };
+  }
+
+  static async getResources(
+    context: string,
+    kind: string,
+    namespace = "",
+    group = "",
+    version = "",
+    labelSelector = "",
+    fieldSelector = ""
+  ): Promise<KubernetesObject[]> {
+    return invoke("list_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      labelSelector: labelSelector,
+      fieldSelector: fieldSelector,
+    });
+  }
+
+  static async getResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    group = "",
+    version = ""
+  ): Promise<KubernetesObject> {
+    return invoke("get_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+    });
+  }
+
+  static async replaceResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    object: unknown,
+    group = "",
+    version = ""
+  ): Promise<KubernetesObject> {
+    return invoke("replace_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      object,
+    });
+  }
+
+  static async patchResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    patch: unknown,
+    patchType: "merge" | "json" | "strategic" = "merge",
+    group = "",
+    version = ""
+  ): Promise<KubernetesObject> {
+    return invoke("patch_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      patchType: patchType,
+      patch,
+    });
+  }
+
+  static async applyResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    object: unknown,
+    force = false,
+    group = "",
+    version = ""
+  ): Promise<KubernetesObject> {
+    return invoke("apply_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      object,
+      force: force,
+    });
+  }
+
+  static async previewChange(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    object: unknown,
+    method: "replace" | "apply" = "replace",
+    group = "",
+    version = ""
+  ): Promise<ChangePreview> {
+    return invoke("preview_change", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      method: method,
+      object,
+    });
+  }
+
+  static async scaleResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    replicas: number,
+    force = false,
+    group = "",
+    version = ""
+  ): Promise<ScaleResult> {
+    return invoke("scale_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      replicas: replicas,
+      force: force,
+    });
+  }
+
+  static getFieldConflicts(error: KubernetesError): FieldConflict[] {
+    return error.kind === "Conflict" ? error.details?.conflicts ?? [] : [];
+  }
+
+  static async deleteResource(
+    context: string,
+    kind: string,
+    namespace: string,
+    name: string,
+    group = "",
+    version = "",
+    gracePeriodSeconds?: number
+  ): Promise<void> {
+    return invoke("delete_resource", {
+      context: context,
+      group: group,
+      version: version,
+      kind: kind,
+      namespace: namespace,
+      name: name,
+      gracePeriodSeconds: gracePeriodSeconds,
+    });
+  }
 }